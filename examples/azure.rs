@@ -0,0 +1,22 @@
+extern crate openai_rust_client;
+
+use openai_rust_client::{ApiKey, AzureDeployment, OpenAIClient};
+use openai_rust_client::endpoints::{ChatMessage, CreateChatCompletionBuilder};
+
+#[tokio::main]
+async fn main() {
+    let api_key = std::env::var("AZURE_API_KEY").unwrap();
+    let resource = std::env::var("AZURE_RESOURCE").unwrap();
+    let deployment = std::env::var("AZURE_DEPLOYMENT").unwrap();
+    let c = OpenAIClient::builder(ApiKey::new(api_key))
+        .azure(AzureDeployment::new(resource, deployment))
+        .build()
+        .unwrap();
+    // the model is determined by the deployment, so this is ignored by Azure
+    let ccc = CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::user("Say this is a test."))
+        .max_tokens(12)
+        .build()
+        .unwrap();
+    println!("{:?}",  c.send(&ccc).await);
+}
@@ -0,0 +1,17 @@
+extern crate openai_rust_client;
+
+use openai_rust_client::{ApiKey, OpenAIClient};
+use openai_rust_client::endpoints::{ChatMessage, CreateChatCompletionBuilder};
+
+#[tokio::main]
+async fn main() {
+    let api_key = std::env::var("API_KEY").unwrap();
+    let c = OpenAIClient::new(ApiKey::new(api_key));
+    let ccc = CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::system("You are a terse assistant."))
+        .message(ChatMessage::user("Say this is a test."))
+        .max_tokens(12)
+        .build()
+        .unwrap();
+    println!("{:?}",  c.send(&ccc).await);
+}
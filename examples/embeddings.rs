@@ -0,0 +1,21 @@
+extern crate openai_rust_client;
+
+use openai_rust_client::{ApiKey, OpenAIClient};
+use openai_rust_client::endpoints::{cosine_similarity, CreateEmbedding, EmbeddingInput, EncodingFormat};
+
+#[tokio::main]
+async fn main() {
+    let api_key = std::env::var("API_KEY").unwrap();
+    let c = OpenAIClient::new(ApiKey::new(api_key));
+    let mut req = CreateEmbedding::new(
+        "text-embedding-3-small",
+        EmbeddingInput::Many {
+            many: vec!["The cat sat on the mat".to_string(), "A feline rested on a rug".to_string()],
+        },
+    );
+    req.encoding_format = Some(EncodingFormat::Base64);
+    let resp = c.send(&req).await.unwrap();
+    println!("{:?}", resp.usage);
+    let vectors = resp.into_vectors();
+    println!("similarity: {}", cosine_similarity(&vectors[0], &vectors[1]));
+}
@@ -1,14 +1,17 @@
 extern crate openai_rust_client;
 
 use openai_rust_client::{ApiKey, OpenAIClient};
-use openai_rust_client::endpoints::{ListEngines};
-use tokio;
+use openai_rust_client::endpoints::{ListModels, RetrieveModel};
 
 #[tokio::main]
 async fn main() {
     let api_key = std::env::var("API_KEY").unwrap();
     let c = OpenAIClient::new(ApiKey::new(api_key));
-    let le = ListEngines {};
-    let es = c.send(le).await;
-    println!("{:?}", es);
-}
\ No newline at end of file
+    let models = c.send(&ListModels {}).await.unwrap();
+    for m in &models.data {
+        println!("{} (owned by {})", m.id, m.owned_by);
+    }
+    if let Some(first) = models.data.first() {
+        println!("{:?}", c.send(&RetrieveModel { id: first.id.clone() }).await);
+    }
+}
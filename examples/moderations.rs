@@ -1,8 +1,7 @@
 extern crate openai_rust_client;
 
 use openai_rust_client::{ApiKey, OpenAIClient};
-use openai_rust_client::endpoints::{CreateCompletionBuilder, Moderations, Prompt};
-use tokio;
+use openai_rust_client::endpoints::Moderations;
 
 #[tokio::main]
 async fn main() {
@@ -0,0 +1,32 @@
+extern crate openai_rust_client;
+
+use futures_util::StreamExt;
+use openai_rust_client::{ApiKey, OpenAIClient};
+use openai_rust_client::endpoints::{CreateCompletionBuilder, Prompt};
+use std::io::Write;
+
+#[tokio::main]
+async fn main() {
+    let api_key = std::env::var("API_KEY").unwrap();
+    let c = OpenAIClient::new(ApiKey::new(api_key));
+    let cc = CreateCompletionBuilder::new("gpt-3.5-turbo-instruct".to_string())
+        .prompt(Prompt::One { one: "Write a haiku about testing.".to_string() })
+        .max_tokens(64)
+        .stream(true)
+        .build()
+        .unwrap();
+    let mut deltas = Box::pin(c.send_stream(&cc).await.unwrap());
+    while let Some(delta) = deltas.next().await {
+        match delta {
+            Ok(choice) => {
+                print!("{}", choice.text);
+                std::io::stdout().flush().unwrap();
+            }
+            Err(e) => {
+                println!("\n{}", e);
+                return;
+            }
+        }
+    }
+    println!();
+}
@@ -0,0 +1,96 @@
+//! Where requests are sent, and how they're authenticated
+
+use crate::{ApiKey, Error, Request};
+use reqwest::Url;
+
+/// The Azure API version used if none is given
+pub const DEFAULT_AZURE_API_VERSION: &str = "2024-10-21";
+
+/// A model deployment on Azure OpenAI. see https://learn.microsoft.com/en-us/azure/ai-services/openai/reference
+#[derive(Debug, Clone)]
+pub struct AzureDeployment {
+    endpoint: String,
+    deployment: String,
+    api_version: String,
+}
+
+impl AzureDeployment {
+    /// A deployment on `https://{resource}.openai.azure.com`
+    pub fn new<R: AsRef<str>, D: Into<String>>(resource: R, deployment: D) -> Self {
+        Self {
+            endpoint: format!("https://{}.openai.azure.com", resource.as_ref()),
+            deployment: deployment.into(),
+            api_version: DEFAULT_AZURE_API_VERSION.to_string(),
+        }
+    }
+
+    /// Use a different host than `{resource}.openai.azure.com`, e.g. a custom subdomain or a gateway in front of it
+    pub fn endpoint<S: Into<String>>(mut self, endpoint: S) -> Self {
+        self.endpoint = endpoint.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// The `api-version` query parameter sent with every request
+    pub fn api_version<S: Into<String>>(mut self, api_version: S) -> Self {
+        self.api_version = api_version.into();
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Backend {
+    /// api.openai.com or a compatible server, authenticated with a bearer token
+    OpenAI { base_url: String },
+    /// An Azure OpenAI deployment, authenticated with an `api-key` header
+    Azure(AzureDeployment),
+}
+
+impl Backend {
+    pub(crate) fn url<R: Request>(&self, req: &R) -> Result<Url, Error> {
+        let endpoint = req.endpoint();
+        check_path(&endpoint)?;
+        let url = match self {
+            Backend::OpenAI { base_url } => parse_url(format!("{}/{}", base_url, endpoint))?,
+            Backend::Azure(azure) => {
+                let url = if req.azure_deployment_scoped() {
+                    format!("{}/openai/deployments/{}/{}", azure.endpoint, azure.deployment, endpoint)
+                } else {
+                    format!("{}/openai/{}", azure.endpoint, endpoint)
+                };
+                let mut url = parse_url(url)?;
+                url.query_pairs_mut().append_pair("api-version", &azure.api_version);
+                url
+            }
+        };
+        Ok(url)
+    }
+
+    pub(crate) fn authorize(&self, http_req: reqwest::RequestBuilder, api_key: &ApiKey) -> reqwest::RequestBuilder {
+        match self {
+            Backend::OpenAI { .. } => http_req.bearer_auth(api_key),
+            Backend::Azure(_) => http_req.header("api-key", api_key.as_str()),
+        }
+    }
+}
+
+/// Rejects `.` and `..` segments, e.g. from an id of `..`. Escaping doesn't help with these: URLs treat
+/// `%2E%2E` as `..` too, so they'd still send the request somewhere other than the endpoint.
+fn check_path(endpoint: &str) -> Result<(), Error> {
+    let path = endpoint.split(['?', '#']).next().unwrap_or_default();
+    let dot_segment = path.split('/').any(|segment| {
+        let segment = segment.to_ascii_lowercase().replace("%2e", ".");
+        segment == "." || segment == ".."
+    });
+    if dot_segment {
+        return Err(Error::ConfigError {
+            err: format!("invalid endpoint {}: it can't contain `.` or `..` segments", endpoint),
+        });
+    }
+    Ok(())
+}
+
+fn parse_url(url: String) -> Result<Url, Error> {
+    Url::parse(&url).map_err(|e| Error::ConfigError {
+        err: format!("invalid url {}: {}", url, e),
+    })
+}
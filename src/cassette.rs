@@ -0,0 +1,266 @@
+//! Recording of real API interactions to a file, and replaying them later, for regression tests that
+//! don't need network access or an api key once recorded.
+//!
+//! Cassettes are JSONL files with one [Interaction] per line. Requests are matched by method, endpoint and
+//! body; JSON bodies are compared structurally so that field order and whitespace don't matter.
+//! Credentials are never written: auth headers are redacted, and the api key is scrubbed from anything else
+//! that gets recorded.
+
+use crate::transport::TransportError;
+use crate::Error;
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Headers whose values are never written to a cassette
+const SENSITIVE_HEADERS: &[&str] = &["authorization", "api-key", "openai-organization", "openai-project"];
+const REDACTED: &str = "[REDACTED]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Send requests for real, appending each interaction to the cassette. Any existing cassette is replaced.
+    Record,
+    /// Answer requests from the cassette without touching the network. A request with no recorded match
+    /// fails with [Error::CassetteError].
+    Replay,
+    /// Send requests for real and record nothing, as if there were no cassette
+    Passthrough,
+}
+
+/// A recorded request and the response it got
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// The url path and query, without the host, e.g. `/v1/chat/completions`
+    pub endpoint: String,
+    pub headers: Vec<(String, String)>,
+    /// The body, if it was JSON
+    pub body: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// The body, if it was valid UTF-8
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// The body, base64-encoded, if it wasn't valid UTF-8
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_base64: Option<String>,
+}
+
+impl RecordedResponse {
+    fn body_bytes(&self) -> Result<Vec<u8>, Error> {
+        match (&self.body, &self.body_base64) {
+            (Some(body), _) => Ok(body.clone().into_bytes()),
+            (None, Some(encoded)) => base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .map_err(|e| Error::CassetteError {
+                    err: format!("invalid base64 body in cassette: {}", e),
+                }),
+            (None, None) => Ok(vec![]),
+        }
+    }
+}
+
+/// A cassette file, used via [OpenAIClientBuilder::cassette](crate::OpenAIClientBuilder::cassette)
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    /// In replay mode, each interaction and whether it has been replayed yet
+    interactions: Mutex<Vec<(Interaction, bool)>>,
+}
+
+impl Cassette {
+    /// Opens the cassette at `path`: for [CassetteMode::Replay] it is read in, for [CassetteMode::Record]
+    /// it is created (or emptied).
+    pub fn open<P: AsRef<Path>>(path: P, mode: CassetteMode) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let interactions = match mode {
+            CassetteMode::Replay => load(&path)?.into_iter().map(|i| (i, false)).collect(),
+            CassetteMode::Record => {
+                File::create(&path).map_err(|e| io_error(&path, e))?;
+                vec![]
+            }
+            CassetteMode::Passthrough => vec![],
+        };
+        Ok(Self {
+            path,
+            mode,
+            interactions: Mutex::new(interactions),
+        })
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub(crate) async fn execute(
+        &self,
+        client: &reqwest::Client,
+        http_req: reqwest::Request,
+    ) -> Result<reqwest::Response, TransportError> {
+        // transport errors aren't recorded, so these go through as they are
+        match self.mode {
+            CassetteMode::Passthrough => Ok(client.execute(http_req).await?),
+            CassetteMode::Replay => Ok(self.replay(&http_req)?),
+            CassetteMode::Record => {
+                let secret = secret(&http_req);
+                let request = record_request(&http_req, secret.as_deref());
+                let resp = client.execute(http_req).await?;
+                let status = resp.status().as_u16();
+                let headers = record_headers(resp.headers(), secret.as_deref());
+                let bytes = resp.bytes().await?;
+                let (body, body_base64) = match std::str::from_utf8(&bytes) {
+                    Ok(text) => (Some(scrub(text, secret.as_deref())), None),
+                    Err(_) => (None, Some(base64::engine::general_purpose::STANDARD.encode(&bytes))),
+                };
+                let response = RecordedResponse {
+                    status,
+                    headers,
+                    body,
+                    body_base64,
+                };
+                let interaction = Interaction { request, response };
+                self.append(&interaction)?;
+                Ok(to_response(&interaction.response)?)
+            }
+        }
+    }
+
+    fn replay(&self, http_req: &reqwest::Request) -> Result<reqwest::Response, Error> {
+        let request = record_request(http_req, None);
+        let mut interactions = self.interactions.lock().unwrap();
+        let found = interactions.iter_mut().find(|(interaction, used)| {
+            !*used
+                && interaction.request.method == request.method
+                && interaction.request.endpoint == request.endpoint
+                && interaction.request.body == request.body
+        });
+        match found {
+            Some((interaction, used)) => {
+                *used = true;
+                to_response(&interaction.response)
+            }
+            None => Err(Error::CassetteError {
+                err: format!(
+                    "no unused interaction in {} matches {} {}",
+                    self.path.display(),
+                    request.method,
+                    request.endpoint
+                ),
+            }),
+        }
+    }
+
+    fn append(&self, interaction: &Interaction) -> Result<(), Error> {
+        let line = serde_json::to_string(interaction).map_err(|e| Error::CassetteError { err: e.to_string() })?;
+        // hold the lock so concurrent requests don't interleave their writes
+        let _guard = self.interactions.lock().unwrap();
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| io_error(&self.path, e))?;
+        writeln!(file, "{}", line).map_err(|e| io_error(&self.path, e))
+    }
+}
+
+fn load(path: &Path) -> Result<Vec<Interaction>, Error> {
+    let file = File::open(path).map_err(|e| io_error(path, e))?;
+    let mut interactions = vec![];
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| io_error(path, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let interaction = serde_json::from_str(&line).map_err(|e| Error::CassetteError {
+            err: format!("{} line {}: {}", path.display(), i + 1, e),
+        })?;
+        interactions.push(interaction);
+    }
+    Ok(interactions)
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> Error {
+    Error::CassetteError {
+        err: format!("{}: {}", path.display(), e),
+    }
+}
+
+/// The credential sent with a request, so it can be scrubbed from everything recorded
+fn secret(http_req: &reqwest::Request) -> Option<String> {
+    let headers = http_req.headers();
+    if let Some(key) = headers.get("api-key").and_then(|v| v.to_str().ok()) {
+        return Some(key.to_string());
+    }
+    headers
+        .get(reqwest::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim_start_matches("Bearer ").to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn scrub(text: &str, secret: Option<&str>) -> String {
+    match secret {
+        Some(secret) => text.replace(secret, REDACTED),
+        None => text.to_string(),
+    }
+}
+
+fn record_headers(headers: &reqwest::header::HeaderMap, secret: Option<&str>) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                scrub(&String::from_utf8_lossy(value.as_bytes()), secret)
+            };
+            (name.as_str().to_string(), value)
+        })
+        .collect()
+}
+
+fn record_request(http_req: &reqwest::Request, secret: Option<&str>) -> RecordedRequest {
+    let url = http_req.url();
+    let endpoint = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    let body = http_req
+        .body()
+        .and_then(|b| b.as_bytes())
+        .and_then(|b| serde_json::from_slice::<Value>(b).ok());
+    let body = match (body, secret) {
+        (Some(body), Some(secret)) => serde_json::from_str(&scrub(&body.to_string(), Some(secret))).ok(),
+        (body, _) => body,
+    };
+    RecordedRequest {
+        method: http_req.method().to_string(),
+        endpoint,
+        headers: record_headers(http_req.headers(), secret),
+        body,
+    }
+}
+
+fn to_response(recorded: &RecordedResponse) -> Result<reqwest::Response, Error> {
+    let mut builder = http::Response::builder().status(recorded.status);
+    for (name, value) in &recorded.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    let resp = builder
+        .body(recorded.body_bytes()?)
+        .map_err(|e| Error::CassetteError { err: e.to_string() })?;
+    Ok(reqwest::Response::from(resp))
+}
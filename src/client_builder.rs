@@ -0,0 +1,184 @@
+use crate::{
+    ApiKey, AzureDeployment, Backend, Cassette, Clock, Error, OpenAIClient, RateLimit, RateLimiter, RetryPolicy,
+    TokioClock, Transport, UsageTracker, DEFAULT_BASE_URL,
+};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Client as ReqwestClient;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Configures an [OpenAIClient]. For the common case of talking to api.openai.com with default settings,
+/// [OpenAIClient::new] is enough.
+pub struct OpenAIClientBuilder {
+    api_key: ApiKey,
+    backend: Backend,
+    organization: Option<String>,
+    project: Option<String>,
+    headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    client: Option<ReqwestClient>,
+    retry_policy: RetryPolicy,
+    clock: Arc<dyn Clock>,
+    rate_limit: Option<RateLimit>,
+    log_request_bodies: bool,
+    cassette: Option<Cassette>,
+}
+
+impl OpenAIClientBuilder {
+    pub fn new(api_key: ApiKey) -> Self {
+        Self {
+            api_key,
+            backend: Backend::OpenAI {
+                base_url: DEFAULT_BASE_URL.to_string(),
+            },
+            organization: None,
+            project: None,
+            headers: vec![],
+            timeout: None,
+            connect_timeout: None,
+            client: None,
+            retry_policy: RetryPolicy::default(),
+            clock: Arc::new(TokioClock),
+            rate_limit: None,
+            log_request_bodies: false,
+            cassette: None,
+        }
+    }
+
+    /// The URL that endpoints are appended to, e.g. a proxy or an OpenAI-compatible server.
+    /// Defaults to [DEFAULT_BASE_URL]
+    pub fn base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.backend = Backend::OpenAI {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        };
+        self
+    }
+
+    /// Send requests to an Azure OpenAI deployment instead of OpenAI. The api key given to [Self::new]
+    /// is then sent as Azure's `api-key` header rather than as a bearer token.
+    pub fn azure(mut self, deployment: AzureDeployment) -> Self {
+        self.backend = Backend::Azure(deployment);
+        self
+    }
+
+    /// Sent as the `OpenAI-Organization` header, for users who belong to multiple organizations
+    pub fn organization<S: Into<String>>(mut self, organization: S) -> Self {
+        self.organization = Some(organization.into());
+        self
+    }
+
+    /// Sent as the `OpenAI-Project` header
+    pub fn project<S: Into<String>>(mut self, project: S) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    /// An extra header to send with every request
+    pub fn header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Timeout for each attempt of a request, from connecting until the response body has been read
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Timeout for establishing a connection. Has no effect if a custom client is supplied via [Self::client],
+    /// in which case configure it on that client instead.
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Use a preconfigured reqwest client, e.g. to share a connection pool or set up a proxy
+    pub fn client(mut self, client: ReqwestClient) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Replaces the default [RetryPolicy]. Use [RetryPolicy::none] to disable retries entirely.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Throttles requests on the client side to stay within the given budgets, shared by everything using the
+    /// client. Off by default.
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Replaces the [Clock] used to wait between retries and for rate limits
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Include request bodies (which contain prompts and other user content) in trace-level events.
+    /// Off by default; only has an effect with the `tracing` feature enabled.
+    pub fn log_request_bodies(mut self, log_request_bodies: bool) -> Self {
+        self.log_request_bodies = log_request_bodies;
+        self
+    }
+
+    /// Record interactions to, or replay them from, a [Cassette] instead of only talking to the server
+    pub fn cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    pub fn build(self) -> Result<OpenAIClient, Error> {
+        let mut headers = HeaderMap::new();
+        let named = vec![
+            ("OpenAI-Organization", self.organization),
+            ("OpenAI-Project", self.project),
+        ];
+        let named = named
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|v| (name.to_string(), v)));
+        for (name, value) in named.chain(self.headers) {
+            let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| Error::ConfigError {
+                err: format!("invalid header name {}: {}", name, e),
+            })?;
+            let header_value = HeaderValue::from_str(&value).map_err(|e| Error::ConfigError {
+                err: format!("invalid value for header {}: {}", name, e),
+            })?;
+            headers.insert(header_name, header_value);
+        }
+
+        let client = match self.client {
+            Some(client) => client,
+            None => {
+                let mut builder = ReqwestClient::builder();
+                if let Some(connect_timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(connect_timeout);
+                }
+                builder.build().map_err(|e| Error::ConfigError { err: e.to_string() })?
+            }
+        };
+
+        let now = self.clock.now();
+        let rate_limiter = self.rate_limit.map(|limit| RateLimiter::new(&limit, now));
+
+        Ok(OpenAIClient {
+            api_key: self.api_key,
+            client,
+            transport: match self.cassette {
+                Some(cassette) => Transport::Cassette(cassette),
+                None => Transport::Http,
+            },
+            backend: self.backend,
+            headers,
+            timeout: self.timeout,
+            retry_policy: self.retry_policy,
+            rate_limiter,
+            clock: self.clock,
+            log_request_bodies: self.log_request_bodies,
+            usage: Arc::new(UsageTracker::default()),
+        })
+    }
+}
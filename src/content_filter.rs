@@ -3,7 +3,7 @@
 //! "recommended" - I'm pretty sure they'll be mad if you don't conform to their recommendations.
 //! See https://beta.openai.com/docs/engines/content-filter
 
-use crate::endpoints::{CreateCompletion, CreateCompletionBuilder, Prompt, CreateCompletionResponse};
+use crate::endpoints::{BuildErrors, CreateCompletion, CreateCompletionBuilder, Prompt, CreateCompletionResponse};
 use crate::{OpenAIClient, Error};
 
 use std::fmt::{Display, Formatter};
@@ -11,7 +11,7 @@ use serde::Serialize;
 
 /// Creates a content filter completion request.
 /// Parameters are as prescribed in https://beta.openai.com/docs/engines/content-filter
-pub fn create_content_filter_request<S: Display>(text: S) -> Result<CreateCompletion, String> {
+pub fn create_content_filter_request<S: Display>(text: S) -> Result<CreateCompletion, BuildErrors> {
     CreateCompletionBuilder::new("content-filter-alpha")
         .max_tokens(1)
         .temperature(0.0)
@@ -52,7 +52,6 @@ const TOXIC_THRESHOLD: f32 = -0.355;
 /// Return the filter label if it can be determined, or an error
 /// In the event of an error you should probably treat the text as "unsafe"
 pub fn determine_filter_label(mut resp: CreateCompletionResponse) -> Result<FilterLabel, ClassificationError> {
-    println!("filter resp: {:?}", resp);
     let label_choice = resp.choices.pop().ok_or(ClassificationError::NoLabelFound)?;
     if ! resp.choices.is_empty() {
         return Err(ClassificationError::TooManyLabels);
@@ -62,9 +61,10 @@ pub fn determine_filter_label(mut resp: CreateCompletionResponse) -> Result<Filt
     } else if label_choice.text == "1" {
         Ok(FilterLabel::Sensitive)
     } else if label_choice.text == "2" {
-        let lps = label_choice.log_probs.ok_or(ClassificationError::MissingLogProbs)?;
-        let lp = lps.get(0).ok_or(ClassificationError::MissingLogProbs)?;
-        let top_lp = lp.top_logprobs.get(0).ok_or(ClassificationError::MissingLogProbs)?;
+        let lp = label_choice.log_probs.ok_or(ClassificationError::MissingLogProbs)?;
+        let top_lp = lp.top_logprobs.first()
+            .and_then(|top| top.as_ref())
+            .ok_or(ClassificationError::MissingLogProbs)?;
         let unsafe_lp = top_lp.get("2").ok_or(ClassificationError::MissingLogProbs)?;
         if unsafe_lp >= &TOXIC_THRESHOLD {
             return Ok(FilterLabel::Unsafe);
@@ -98,17 +98,12 @@ pub fn determine_filter_label(mut resp: CreateCompletionResponse) -> Result<Filt
 /// Runs all the steps in https://beta.openai.com/docs/engines/content-filter for you
 #[deprecated(since="0.1.1", note="Use the moderations endpoint instead")]
 pub async fn filter_content<S: Display>(text: S, c: &OpenAIClient) -> Result<FilterLabel, String> {
-    let req = create_content_filter_request(text)?;
+    let req = create_content_filter_request(text).map_err(|e| e.to_string())?;
     let resp = match c.send(&req).await {
         Ok(r) => r,
         Err(e) => {
             match e {
-                Error::HttpError { err } => {
-                    // retry once
-                    println!("Error getting content filtering; going to retry once. Err: {}", err);
-                    c.send(&req).await.map_err(|e| e.to_string())?
-                }
-                Error::ClientError { err, status } => {
+                Error::ClientError { err, status, .. } | Error::ServerError { err, status, .. } => {
                     return Err(format!(
                         "Error making content filter request: status {status} | error {err}", status=status, err=err
                     ))
@@ -116,6 +111,10 @@ pub async fn filter_content<S: Display>(text: S, c: &OpenAIClient) -> Result<Fil
                 Error::DeserializeError { err } => {
                     return Err(format!("Error deserializing content filter response: {err}", err=err))
                 }
+                // the client has already retried transient failures by this point
+                e => {
+                    return Err(format!("Error making content filter request: {e}", e=e))
+                }
             }
         }
     };
@@ -0,0 +1,561 @@
+use crate::endpoints::response::{FinishReason, ObjectKind};
+use crate::endpoints::create_completion::NullableOneOrMany;
+use crate::endpoints::response_format::ResponseFormat;
+use crate::endpoints::tools::{Tool, ToolCall, ToolCallDelta, ToolChoice};
+use crate::endpoints::validation::{BuildError, BuildErrors, Validator};
+use crate::endpoints::{Stop, StreamOptions, Usage};
+use crate::rate_limit::estimate_tokens;
+use crate::{Method, Request, StreamRequest};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Who authored a [ChatMessage]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+    Tool,
+}
+
+/// How much detail the model should use when looking at an image
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageDetail {
+    Auto,
+    Low,
+    High,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImageUrl {
+    /// Either a URL of the image or the base64 encoded image data, as a `data:` URL
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<ImageDetail>,
+}
+
+/// One piece of a multi-part message
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+/// The content of a user message: either plain text, or a list of parts (e.g. text and images)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum ChatContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl From<String> for ChatContent {
+    fn from(s: String) -> Self {
+        ChatContent::Text(s)
+    }
+}
+
+impl From<&str> for ChatContent {
+    fn from(s: &str) -> Self {
+        ChatContent::Text(s.to_string())
+    }
+}
+
+impl From<Vec<ContentPart>> for ChatContent {
+    fn from(parts: Vec<ContentPart>) -> Self {
+        ChatContent::Parts(parts)
+    }
+}
+
+/// A single message in a conversation. The role determines which fields are allowed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "role", rename_all = "lowercase")]
+pub enum ChatMessage {
+    /// Instructions for the model
+    System {
+        content: String,
+        /// An optional name for the participant, to differentiate between participants of the same role
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+    /// A message from the end user
+    User {
+        content: ChatContent,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+    /// A message from the model. This is also what you get back in a [ChatChoice]
+    Assistant {
+        /// Can be absent, e.g. if the model only called tools
+        #[serde(default)]
+        content: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        /// The tools the model called. Reply to each with a [ChatMessage::tool] message.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tool_calls: Vec<ToolCall>,
+        /// The model's explanation, instead of `content`, of why it won't produce the requested output
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        refusal: Option<String>,
+    },
+    /// The result of a tool call the model asked for
+    Tool {
+        content: String,
+        /// The id of the tool call this is responding to
+        tool_call_id: String,
+    },
+}
+
+impl ChatMessage {
+    pub fn system<S: Into<String>>(content: S) -> Self {
+        ChatMessage::System {
+            content: content.into(),
+            name: None,
+        }
+    }
+
+    pub fn user<C: Into<ChatContent>>(content: C) -> Self {
+        ChatMessage::User {
+            content: content.into(),
+            name: None,
+        }
+    }
+
+    pub fn assistant<S: Into<String>>(content: S) -> Self {
+        ChatMessage::Assistant {
+            content: Some(content.into()),
+            name: None,
+            tool_calls: vec![],
+            refusal: None,
+        }
+    }
+
+    pub fn tool<I: Into<String>, S: Into<String>>(tool_call_id: I, content: S) -> Self {
+        ChatMessage::Tool {
+            content: content.into(),
+            tool_call_id: tool_call_id.into(),
+        }
+    }
+
+    pub fn role(&self) -> Role {
+        match self {
+            ChatMessage::System { .. } => Role::System,
+            ChatMessage::User { .. } => Role::User,
+            ChatMessage::Assistant { .. } => Role::Assistant,
+            ChatMessage::Tool { .. } => Role::Tool,
+        }
+    }
+
+    /// The tool calls in an assistant message; empty for any other message
+    pub fn tool_calls(&self) -> &[ToolCall] {
+        match self {
+            ChatMessage::Assistant { tool_calls, .. } => tool_calls,
+            _ => &[],
+        }
+    }
+
+    /// Why the model declined to answer, in an assistant message that is a refusal
+    pub fn refusal(&self) -> Option<&str> {
+        match self {
+            ChatMessage::Assistant { refusal, .. } => refusal.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The text of this message, if it is plain text
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            ChatMessage::System { content, .. } | ChatMessage::Tool { content, .. } => Some(content),
+            ChatMessage::User { content, .. } => match content {
+                ChatContent::Text(t) => Some(t),
+                ChatContent::Parts(_) => None,
+            },
+            ChatMessage::Assistant { content, .. } => content.as_deref(),
+        }
+    }
+}
+
+/// Represents the create chat completion endpoint. see https://platform.openai.com/docs/api-reference/chat/create
+/// use [CreateChatCompletionBuilder] to create
+#[derive(Serialize, Debug)]
+pub struct CreateChatCompletion {
+    /// name of the model to use; e.g. gpt-4o-mini
+    model: String,
+
+    /// The conversation so far
+    messages: Vec<ChatMessage>,
+
+    /// The maximum number of tokens to generate in the completion.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u16>,
+
+    /// What sampling temperature to use, between 0 and 2. Higher values means the model will take more risks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+
+    /// An alternative to sampling with temperature, called nucleus sampling, where the model considers the results of the tokens with top_p probability mass.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+
+    /// How many completions to generate for each prompt.
+    /// Defaults to 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n: Option<u16>,
+
+    /// Stream back partial progress as server-sent events. Use [OpenAIClient::send_stream](crate::OpenAIClient::send_stream)
+    /// to consume the response.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+
+    /// Options for the streamed response. Only valid when `stream` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+
+    /// Up to 4 sequences where the API will stop generating further tokens.
+    #[serde(skip_serializing_if = "NullableOneOrMany::is_none")]
+    stop: Stop,
+
+    /// Number between -2.0 and 2.0. Positive values penalize new tokens based on whether they appear in the text
+    /// so far, increasing the model's likelihood to talk about new topics.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+
+    /// Number between -2.0 and 2.0. Positive values penalize new tokens based on their existing frequency in the
+    /// text so far, decreasing the model's likelihood to repeat the same line verbatim.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+
+    /// Modify the likelihood of specified tokens appearing in the completion.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logit_bias: Option<HashMap<String, f32>>,
+
+    /// A unique identifier representing your end-user, which will help OpenAI to monitor and detect abuse.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<String>,
+
+    /// Tools the model may call, at most 128
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Tool>,
+
+    /// Whether, and which, tools the model should call. Only valid with `tools`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<ToolChoice>,
+
+    /// Whether the model may call several tools in one reply. Defaults to true.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parallel_tool_calls: Option<bool>,
+
+    /// The format of the reply, e.g. JSON following a schema. see [OpenAIClient::send_structured](crate::OpenAIClient::send_structured)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+}
+
+impl CreateChatCompletion {
+    pub fn response_format(&self) -> Option<&ResponseFormat> {
+        self.response_format.as_ref()
+    }
+
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    pub(crate) fn push_message(&mut self, message: ChatMessage) {
+        self.messages.push(message);
+    }
+
+    pub(crate) fn tools_mut(&mut self) -> &mut Vec<Tool> {
+        &mut self.tools
+    }
+
+    pub(crate) fn tool_choice_mut(&mut self) -> &mut Option<ToolChoice> {
+        &mut self.tool_choice
+    }
+
+    /// Checks the parameters against the API's documented limits, including the model's maximum output tokens
+    /// where it is known, and returns every violation rather than just the first
+    pub fn validate(&self) -> Result<(), BuildErrors> {
+        let mut v = Validator::default();
+        if self.messages.is_empty() {
+            v.push(BuildError::Missing { field: "messages" });
+        }
+        v.max_tokens(&self.model, self.max_tokens);
+        v.range("temperature", self.temperature, 0.0, 2.0);
+        v.range("top_p", self.top_p, 0.0, 1.0);
+        v.range("presence_penalty", self.presence_penalty, -2.0, 2.0);
+        v.range("frequency_penalty", self.frequency_penalty, -2.0, 2.0);
+        v.logit_bias(self.logit_bias.as_ref());
+        if let NullableOneOrMany::Many { many } = &self.stop {
+            v.count("stop", many.len(), 4);
+        }
+        v.count("tools", self.tools.len(), 128);
+        match &self.tool_choice {
+            Some(ToolChoice::Required) | Some(ToolChoice::Function(_)) if self.tools.is_empty() => {
+                v.push(BuildError::Missing { field: "tools" });
+            }
+            Some(ToolChoice::Function(name)) if !self.tools.iter().any(|t| t.name() == name) => {
+                v.push(BuildError::UnknownTool { name: name.clone() });
+            }
+            _ => {}
+        }
+        if !self.stream && self.stream_options.is_some() {
+            v.push(BuildError::RequiresStream { field: "stream_options" });
+        }
+        v.finish()
+    }
+}
+
+/// The output tokens a chat request without `max_tokens` is assumed to use when rate limiting: the most that
+/// older chat models can generate, and longer than most replies
+const DEFAULT_OUTPUT_ESTIMATE: u32 = 4096;
+
+/// A ChatChoice is effectively a single reply from the model
+#[derive(Deserialize, Debug)]
+pub struct ChatChoice {
+    /// The index of this choice among the `n` requested
+    pub index: usize,
+    /// The generated message. This will always be [ChatMessage::Assistant]
+    pub message: ChatMessage,
+    pub finish_reason: Option<FinishReason>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CreateChatCompletionResponse {
+    pub id: String,
+    pub object: ObjectKind,
+    pub created: u64,
+    pub model: String,
+    pub choices: Vec<ChatChoice>,
+    /// Token usage for the request
+    pub usage: Option<Usage>,
+}
+
+/// The part of a message that arrived in a single streamed event
+#[derive(Deserialize, Debug)]
+pub struct ChatMessageDelta {
+    /// Only present on the first delta of each choice
+    pub role: Option<Role>,
+    pub content: Option<String>,
+    /// Part of a refusal, in place of `content`
+    pub refusal: Option<String>,
+    /// Pieces of the tool calls the model is making
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCallDelta>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChatChoiceDelta {
+    pub index: usize,
+    pub delta: ChatMessageDelta,
+    /// This is `None` on all but the last delta of each choice
+    pub finish_reason: Option<FinishReason>,
+}
+
+/// A single event of a streamed chat completion
+#[derive(Deserialize, Debug)]
+pub struct CreateChatCompletionChunk {
+    pub id: String,
+    pub object: ObjectKind,
+    pub created: u64,
+    pub model: String,
+    pub choices: Vec<ChatChoiceDelta>,
+    /// Token usage for the whole request, on an extra last event with no choices, if the API was asked for it
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+impl Request for CreateChatCompletion {
+    type Resp = CreateChatCompletionResponse;
+    type Body = Self;
+    const METHOD: Method = Method::POST;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        Cow::from("chat/completions")
+    }
+
+    fn azure_deployment_scoped(&self) -> bool {
+        true
+    }
+
+    fn body(&self) -> Option<&Self::Body> {
+        Some(self)
+    }
+
+    fn usage(resp: &Self::Resp) -> Option<&Usage> {
+        resp.usage.as_ref()
+    }
+
+    /// The text of every message and the tool definitions, plus `max_tokens` for each of the `n` choices.
+    /// Without `max_tokens` the reply's length is only bounded by the model, so 4096 tokens are assumed.
+    /// Images aren't counted.
+    fn estimated_tokens(&self) -> u32 {
+        let prompt = self
+            .messages
+            .iter()
+            .map(|message| {
+                // a few tokens of framing per message
+                let text = match message {
+                    ChatMessage::User { content: ChatContent::Parts(parts), .. } => parts
+                        .iter()
+                        .map(|part| match part {
+                            ContentPart::Text { text } => estimate_tokens(text),
+                            ContentPart::ImageUrl { .. } => 0,
+                        })
+                        .sum(),
+                    message => message.text().map(estimate_tokens).unwrap_or(0),
+                };
+                text.saturating_add(4)
+            })
+            .fold(0, u32::saturating_add);
+        let tools = if self.tools.is_empty() {
+            0
+        } else {
+            serde_json::to_string(&self.tools).map(|t| estimate_tokens(&t)).unwrap_or(0)
+        };
+        let generated = self.max_tokens.map_or(DEFAULT_OUTPUT_ESTIMATE, u32::from) * u32::from(self.n.unwrap_or(1));
+        prompt.saturating_add(tools).saturating_add(generated)
+    }
+}
+
+impl StreamRequest for CreateChatCompletion {
+    type Event = CreateChatCompletionChunk;
+    type Delta = ChatChoiceDelta;
+
+    fn deltas(event: Self::Event) -> Vec<Self::Delta> {
+        event.choices
+    }
+    fn event_usage(event: &Self::Event) -> Option<&Usage> {
+        event.usage.as_ref()
+    }
+}
+
+pub struct CreateChatCompletionBuilder {
+    create_chat_completion: CreateChatCompletion,
+}
+
+impl CreateChatCompletionBuilder {
+    pub fn new<S: Into<String>>(model: S) -> Self {
+        Self {
+            create_chat_completion: CreateChatCompletion {
+                model: model.into(),
+                messages: vec![],
+                max_tokens: None,
+                temperature: None,
+                top_p: None,
+                n: None,
+                stream: false,
+                stream_options: None,
+                stop: NullableOneOrMany::None,
+                presence_penalty: None,
+                frequency_penalty: None,
+                logit_bias: None,
+                user: None,
+                tools: vec![],
+                tool_choice: None,
+                parallel_tool_calls: None,
+                response_format: None,
+            },
+        }
+    }
+
+    pub fn messages(mut self, messages: Vec<ChatMessage>) -> Self {
+        self.create_chat_completion.messages = messages;
+        self
+    }
+
+    /// Appends a single message to the conversation
+    pub fn message(mut self, message: ChatMessage) -> Self {
+        self.create_chat_completion.messages.push(message);
+        self
+    }
+
+    pub fn max_tokens(mut self, max_tokens: u16) -> Self {
+        self.create_chat_completion.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.create_chat_completion.temperature = Some(temperature);
+        self
+    }
+
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        self.create_chat_completion.top_p = Some(top_p);
+        self
+    }
+
+    pub fn n(mut self, n: u16) -> Self {
+        self.create_chat_completion.n = Some(n);
+        self
+    }
+
+    pub fn stream(mut self, stream: bool) -> Self {
+        self.create_chat_completion.stream = stream;
+        self
+    }
+
+    pub fn stream_options(mut self, stream_options: StreamOptions) -> Self {
+        self.create_chat_completion.stream_options = Some(stream_options);
+        self
+    }
+
+    pub fn stop(mut self, stop: Stop) -> Self {
+        self.create_chat_completion.stop = stop;
+        self
+    }
+
+    pub fn presence_penalty(mut self, presence_penalty: f32) -> Self {
+        self.create_chat_completion.presence_penalty = Some(presence_penalty);
+        self
+    }
+
+    pub fn frequency_penalty(mut self, frequency_penalty: f32) -> Self {
+        self.create_chat_completion.frequency_penalty = Some(frequency_penalty);
+        self
+    }
+
+    pub fn logit_bias(mut self, logit_bias: HashMap<String, f32>) -> Self {
+        self.create_chat_completion.logit_bias = Some(logit_bias);
+        self
+    }
+
+    pub fn user(mut self, user: String) -> Self {
+        self.create_chat_completion.user = Some(user);
+        self
+    }
+
+    pub fn tools(mut self, tools: Vec<Tool>) -> Self {
+        self.create_chat_completion.tools = tools;
+        self
+    }
+
+    /// Adds a single tool the model may call
+    pub fn tool(mut self, tool: Tool) -> Self {
+        self.create_chat_completion.tools.push(tool);
+        self
+    }
+
+    pub fn tool_choice(mut self, tool_choice: ToolChoice) -> Self {
+        self.create_chat_completion.tool_choice = Some(tool_choice);
+        self
+    }
+
+    pub fn parallel_tool_calls(mut self, parallel_tool_calls: bool) -> Self {
+        self.create_chat_completion.parallel_tool_calls = Some(parallel_tool_calls);
+        self
+    }
+
+    pub fn response_format<F: Into<ResponseFormat>>(mut self, response_format: F) -> Self {
+        self.create_chat_completion.response_format = Some(response_format.into());
+        self
+    }
+
+    /// Checks every parameter and returns the request, or all of the problems found. see [CreateChatCompletion::validate]
+    pub fn build(self) -> Result<CreateChatCompletion, BuildErrors> {
+        self.create_chat_completion.validate()?;
+        Ok(self.create_chat_completion)
+    }
+}
@@ -1,10 +1,15 @@
-use crate::{Method, Request};
-use serde::ser::SerializeMap;
-use serde::{Deserialize, Serialize, Serializer};
+use crate::endpoints::response::{FinishReason, ObjectKind};
+use crate::endpoints::validation::{BuildError, BuildErrors, Validator};
+use crate::endpoints::Usage;
+use crate::rate_limit::estimate_tokens;
+use crate::{Method, Request, StreamRequest};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::borrow::Cow;
 use std::collections::HashMap;
 
+#[derive(Debug, Clone, Default)]
 pub enum NullableOneOrMany<T> {
+    #[default]
     None,
     One { one: T },
     Many { many: Vec<T> },
@@ -26,136 +31,247 @@ where
     }
 }
 
+impl<'de, T> Deserialize<'de> for NullableOneOrMany<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr<T> {
+            Many(Vec<T>),
+            One(T),
+        }
+
+        Ok(match Option::<Repr<T>>::deserialize(deserializer)? {
+            None => NullableOneOrMany::None,
+            Some(Repr::One(one)) => NullableOneOrMany::One { one },
+            Some(Repr::Many(many)) => NullableOneOrMany::Many { many },
+        })
+    }
+}
+
+impl<T> NullableOneOrMany<T> {
+    pub fn is_none(&self) -> bool {
+        matches!(self, NullableOneOrMany::None)
+    }
+}
+
 pub type Prompt = NullableOneOrMany<String>;
 pub type Stop = NullableOneOrMany<String>;
 
-/// Represents the create completion endpoint. see https://beta.openai.com/docs/api-reference/completions/create
-/// use [CreateCompletionBuilder] to create
+/// Options for streamed responses, only valid when streaming
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamOptions {
+    /// Send an extra event before `[DONE]` with the token usage for the whole request, and no choices
+    pub include_usage: bool,
+}
 
+/// Represents the create completion endpoint. see https://platform.openai.com/docs/api-reference/completions/create
+/// use [CreateCompletionBuilder] to create.
+///
+/// This can also be deserialized from the same JSON it serializes to, e.g. to keep requests in config files.
+/// A deserialized request is [validated](Self::validate) just like a built one.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(remote = "Self")]
 pub struct CreateCompletion {
-    /// name of the engine to use; e.g. text-davinci-002
-    engine_id: String,
+    /// name of the model to use; e.g. gpt-3.5-turbo-instruct
+    model: String,
 
     /// text prompt. this has certain limits I don't understand well yet.
     /// Can be empty (for blank prompt), a single, or multiple prompts
+    #[serde(default, skip_serializing_if = "NullableOneOrMany::is_none")]
     prompt: Prompt,
 
     /// Suffix that comes after the completion text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     suffix: Option<String>,
 
     /// The maximum number of tokens to generate in the completion.
     /// Default 16
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u16>,
 
     /// What sampling temperature to use. Higher values means the model will take more risks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
 
     /// An alternative to sampling with temperature, called nucleus sampling, where the model considers the results of the tokens with top_p probability mass.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     top_p: Option<f32>,
 
     /// How many completions to generate for each prompt.
     /// Defaults to 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     n: Option<u16>,
 
-    // not implemented: stream
+    /// Stream back partial progress as server-sent events. Use [OpenAIClient::send_stream](crate::OpenAIClient::send_stream)
+    /// to consume the response.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+
+    /// Options for the streamed response. Only valid when `stream` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+
     /// Include the log probabilities on the logprobs most likely tokens, as well the chosen tokens.
     /// The docs say there's a max of 5; but the docs also mandate setting this to 10 for the content filter
     /// endpoint and mention that you can potentially ask for more than 5 if you ask them nicely.
+    #[serde(rename = "logprobs", default, skip_serializing_if = "Option::is_none")]
     log_probs: Option<u16>,
 
     /// Echo back the prompt in addition to the completion
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     echo: bool,
 
     /// Up to 4 sequences where the API will stop generating further tokens.
     /// The returned text will not contain the stop sequence.
+    #[serde(default, skip_serializing_if = "NullableOneOrMany::is_none")]
     stop: Stop,
 
     /// Number between -2.0 and 2.0. Positive values penalize new tokens based on whether they appear in the text
     /// so far, increasing the model's likelihood to talk about new topics.
     ///
     /// Default 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     presence_penalty: Option<f32>,
 
+    /// Number between -2.0 and 2.0. Positive values penalize new tokens based on their existing frequency in the
+    /// text so far, decreasing the model's likelihood to repeat the same line verbatim.
+    ///
+    /// Default 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+
     /// Generates best_of completions server-side and returns the "best"
     /// (the one with the lowest log probability per token). Results cannot be streamed.
     ///
     /// When used with n, best_of controls the number of candidate completions and n specifies how many to return –
     /// best_of must be greater than n.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     best_of: Option<u16>,
 
     /// Modify the likelihood of specified tokens appearing in the completion.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     logit_bias: Option<HashMap<String, f32>>,
 
+    /// If specified, the system will make a best effort to sample deterministically, such that repeated requests
+    /// with the same seed and parameters should return the same result. Determinism is not guaranteed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+
     /// A unique identifier representing your end-user, which will help OpenAI to monitor and detect abuse.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     user: Option<String>,
 }
 
 impl Serialize for CreateCompletion {
-    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
-    where
-        S: Serializer,
-    {
-        let mut seq = serializer.serialize_map(None)?;
-        match self.prompt {
-            NullableOneOrMany::None => {}
-            _ => {
-                seq.serialize_entry("prompt", &self.prompt)?;
-            }
-        }
-        if self.suffix.is_some() {
-            seq.serialize_entry("suffix", &self.suffix)?;
-        }
-        if self.max_tokens.is_some() {
-            seq.serialize_entry("max_tokens", &self.max_tokens)?;
-        }
-        if self.temperature.is_some() {
-            seq.serialize_entry("temperature", &self.temperature)?;
-        }
-        if self.top_p.is_some() {
-            seq.serialize_entry("top_p", &self.top_p)?;
-        }
-        if self.n.is_some() {
-            seq.serialize_entry("n", &self.n)?;
-        }
-        if self.log_probs.is_some() {
-            seq.serialize_entry("logprobs", &self.log_probs)?;
-        }
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        CreateCompletion::serialize(self, serializer)
+    }
+}
 
-        seq.serialize_entry("echo", &self.echo)?;
-        if self.presence_penalty.is_some() {
-            seq.serialize_entry("presence_penalty", &self.presence_penalty)?;
+impl<'de> Deserialize<'de> for CreateCompletion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let req = CreateCompletion::deserialize(deserializer)?;
+        req.validate().map_err(serde::de::Error::custom)?;
+        Ok(req)
+    }
+}
+
+impl CreateCompletion {
+    /// Checks the parameters against the API's documented limits, including the model's maximum output tokens
+    /// where it is known, and returns every violation rather than just the first. [CreateCompletionBuilder::build]
+    /// and deserializing call this.
+    pub fn validate(&self) -> Result<(), BuildErrors> {
+        let mut v = Validator::default();
+        v.max_tokens(&self.model, self.max_tokens);
+        v.range("temperature", self.temperature, 0.0, 2.0);
+        v.range("top_p", self.top_p, 0.0, 1.0);
+        v.range("presence_penalty", self.presence_penalty, -2.0, 2.0);
+        v.range("frequency_penalty", self.frequency_penalty, -2.0, 2.0);
+        v.logit_bias(self.logit_bias.as_ref());
+        if let NullableOneOrMany::Many { many } = &self.stop {
+            v.count("stop", many.len(), 4);
         }
-        if self.best_of.is_some() {
-            seq.serialize_entry("best_of", &self.best_of)?;
+        if let (Some(n), Some(best_of)) = (self.n, self.best_of) {
+            if best_of <= n {
+                v.push(BuildError::BestOfNotGreaterThanN { best_of, n });
+            }
         }
-        if self.logit_bias.is_some() {
-            seq.serialize_entry("logit", &self.logit_bias)?;
+        if self.stream && self.best_of.is_some() {
+            v.push(BuildError::IncompatibleWithStream { field: "best_of" });
         }
-        if self.user.is_some() {
-            seq.serialize_entry("user", &self.user)?;
+        if !self.stream && self.stream_options.is_some() {
+            v.push(BuildError::RequiresStream { field: "stream_options" });
         }
-        seq.end()
+        v.finish()
     }
 }
 
-
 #[derive(Deserialize, Debug)]
 pub struct LogProbs {
     /// The list of tokens from the completion.
     /// The concatenation of these is the full response; these were the tokens the engine used to create it
     pub tokens: Vec<String>,
 
-    /// the logprobs of the actual tokens selected
-    pub token_logprobs: Vec<f32>,
+    /// the logprobs of the actual tokens selected. With `echo`, the first token of the prompt has no logprob
+    pub token_logprobs: Vec<Option<f32>>,
 
     /// the top choices for each token. Each element here should be a map of the top N or N+1 tokens by log probability,
     /// where N was given in the request. The actually-selected token is always included, so if it is was not one of the
-    /// top N choices, it will be added (thus making the map N+1 elements)
-    pub top_logprobs: Vec<HashMap<String, f32>>,
+    /// top N choices, it will be added (thus making the map N+1 elements).
+    /// As with `token_logprobs`, the first element is missing when the prompt is echoed
+    pub top_logprobs: Vec<Option<HashMap<String, f32>>>,
 
     /// The offset of the token in the text. This includes the given prompt; that is to say,
     /// if you have a 20 character prompt, the first element of this array will be 20.
-    pub text_offset: Vec<u16>,
+    pub text_offset: Vec<u32>,
+}
+
+impl LogProbs {
+    /// The probability (rather than log probability) of the token chosen at `position`
+    pub fn probability(&self, position: usize) -> Option<f32> {
+        self.token_logprobs.get(position).copied().flatten().map(f32::exp)
+    }
+
+    /// The probability of each chosen token, in order. see [Self::probability]
+    pub fn probabilities(&self) -> Vec<Option<f32>> {
+        self.token_logprobs.iter().map(|lp| lp.map(f32::exp)).collect()
+    }
+
+    /// The perplexity of the tokens: `exp` of the negative mean log probability. Lower means the model was
+    /// more confident. Tokens without a logprob are skipped; returns `None` if there are none left.
+    pub fn perplexity(&self) -> Option<f32> {
+        let known: Vec<f32> = self.token_logprobs.iter().flatten().copied().collect();
+        if known.is_empty() {
+            return None;
+        }
+        let mean = known.iter().sum::<f32>() / known.len() as f32;
+        Some((-mean).exp())
+    }
+
+    /// The candidate tokens at `position` with their log probabilities, most likely first
+    pub fn alternatives(&self, position: usize) -> Vec<(&str, f32)> {
+        let mut alternatives: Vec<(&str, f32)> = match self.top_logprobs.get(position) {
+            Some(Some(top)) => top.iter().map(|(token, lp)| (token.as_str(), *lp)).collect(),
+            _ => vec![],
+        };
+        alternatives.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        alternatives
+    }
+
+    /// The 0-based rank of the chosen token among the alternatives at `position`, i.e. 0 if the model picked its
+    /// most likely token. `None` if there are no alternatives recorded for that position.
+    pub fn rank(&self, position: usize) -> Option<usize> {
+        let chosen = self.tokens.get(position)?;
+        self.alternatives(position)
+            .iter()
+            .position(|(token, _)| token == chosen)
+    }
 }
 
 /// A Choice is effectively a completion.
@@ -166,19 +282,24 @@ pub struct Choice {
     /// The index of the prompt this Choice was generated for
     pub index: usize,
 
-    /// Log probabilities (if present)
-    pub log_probs: Option<Vec<LogProbs>>,
+    /// Log probabilities, if they were requested
+    #[serde(rename = "logprobs", default)]
+    pub log_probs: Option<LogProbs>,
 
-    pub finish_reason: String,
+    /// Why the model stopped generating. This is `None` on all but the last delta of a streamed response.
+    pub finish_reason: Option<FinishReason>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct CreateCompletionResponse {
     pub id: String,
-    pub object: String,
+    pub object: ObjectKind,
     pub created: u64,
     pub model: String,
     pub choices: Vec<Choice>,
+    /// Token usage for the request. When streaming, only sent (on an extra last event, with no choices) if
+    /// `stream_options.include_usage` is set.
+    pub usage: Option<Usage>,
 }
 
 impl Request for CreateCompletion {
@@ -186,193 +307,169 @@ impl Request for CreateCompletion {
     type Body = Self;
     const METHOD: Method = Method::POST;
 
-    fn endpoint(&self) -> Cow<str> {
-        Cow::from(format!("engines/{}/completions", self.engine_id))
+    fn endpoint(&self) -> Cow<'_, str> {
+        Cow::from("completions")
+    }
+
+    fn usage(resp: &Self::Resp) -> Option<&Usage> {
+        resp.usage.as_ref()
+    }
+
+    /// Each prompt, plus `max_tokens` (default 16) for each of the `max(n, best_of)` completions generated for it
+    fn estimated_tokens(&self) -> u32 {
+        let prompts = match &self.prompt {
+            NullableOneOrMany::None => vec![""],
+            NullableOneOrMany::One { one } => vec![one.as_str()],
+            NullableOneOrMany::Many { many } => many.iter().map(String::as_str).collect(),
+        };
+        let completions = self.n.unwrap_or(1).max(self.best_of.unwrap_or(1));
+        let generated = u32::from(self.max_tokens.unwrap_or(16)) * u32::from(completions);
+        prompts
+            .into_iter()
+            .map(|prompt| estimate_tokens(prompt).saturating_add(generated))
+            .fold(0, u32::saturating_add)
+    }
+
+    fn azure_deployment_scoped(&self) -> bool {
+        true
     }
 
     fn body(&self) -> Option<&Self::Body> {
-        Some(&self)
+        Some(self)
+    }
+}
+
+impl StreamRequest for CreateCompletion {
+    type Event = CreateCompletionResponse;
+    type Delta = Choice;
+
+    fn deltas(event: Self::Event) -> Vec<Self::Delta> {
+        event.choices
+    }
+
+    fn event_usage(event: &Self::Event) -> Option<&Usage> {
+        event.usage.as_ref()
     }
 }
 
 pub struct CreateCompletionBuilder {
-    create_completion: Result<CreateCompletion, String>,
+    create_completion: CreateCompletion,
 }
 
 impl CreateCompletionBuilder {
-    pub fn new<S:Into<String>>(engine_id: S) -> Self {
+    pub fn new<S:Into<String>>(model: S) -> Self {
         Self {
-            create_completion: Ok(CreateCompletion {
-                engine_id: engine_id.into(),
+            create_completion: CreateCompletion {
+                model: model.into(),
                 prompt: NullableOneOrMany::None,
                 suffix: None,
                 max_tokens: None,
                 temperature: None,
                 top_p: None,
                 n: None,
+                stream: false,
+                stream_options: None,
                 log_probs: None,
                 echo: false,
                 stop: NullableOneOrMany::None,
                 presence_penalty: None,
+                frequency_penalty: None,
                 best_of: None,
                 logit_bias: None,
+                seed: None,
                 user: None,
-            }),
+            },
         }
     }
 
     pub fn prompt(mut self, prompt: Prompt) -> Self {
-        match self.create_completion {
-            Ok(ref mut cc) => {
-                cc.prompt = prompt;
-                self
-            }
-            Err(_) => self,
-        }
+        self.create_completion.prompt = prompt;
+        self
     }
 
     pub fn suffix(mut self, suffix: String) -> Self {
-        match self.create_completion {
-            Ok(ref mut cc) => {
-                cc.suffix = Some(suffix);
-                self
-            }
-            Err(_) => self,
-        }
+        self.create_completion.suffix = Some(suffix);
+        self
     }
 
     pub fn max_tokens(mut self, max_tokens: u16) -> Self {
-        match self.create_completion {
-            Ok(ref mut cc) => {
-                if max_tokens > 4096 {
-                    self.create_completion =
-                        Err("Max tokens cannot exceed 4096 on any model".to_string());
-                    self
-                } else {
-                    cc.max_tokens = Some(max_tokens);
-                    self
-                }
-            }
-            Err(_) => self,
-        }
+        self.create_completion.max_tokens = Some(max_tokens);
+        self
     }
 
     pub fn temperature(mut self, temperature: f32) -> Self {
-        match self.create_completion {
-            Ok(ref mut cc) => {
-                if !(0.0..=1.0).contains(&temperature) {
-                    self.create_completion =
-                        Err("Temperature must be in range [0, 1.0]".to_string());
-                } else {
-                    cc.temperature = Some(temperature);
-                }
-                self
-            }
-            Err(_) => self,
-        }
+        self.create_completion.temperature = Some(temperature);
+        self
     }
+
     pub fn top_p(mut self, top_p: f32) -> Self {
-        match self.create_completion {
-            Ok(ref mut cc) => {
-                if !(0.0..=1.0).contains(&top_p) {
-                    self.create_completion = Err("top_p must be in range [0, 1.0]".to_string());
-                } else {
-                    cc.top_p = Some(top_p);
-                }
-                self
-            }
-            Err(_) => self,
-        }
+        self.create_completion.top_p = Some(top_p);
+        self
     }
+
     pub fn n(mut self, n: u16) -> Self {
-        match self.create_completion {
-            Ok(ref mut cc) => {
-                cc.n = Some(n);
-                self
-            }
-            Err(_) => self,
-        }
+        self.create_completion.n = Some(n);
+        self
+    }
+
+    pub fn stream(mut self, stream: bool) -> Self {
+        self.create_completion.stream = stream;
+        self
+    }
+
+    pub fn stream_options(mut self, stream_options: StreamOptions) -> Self {
+        self.create_completion.stream_options = Some(stream_options);
+        self
     }
 
     pub fn log_probs(mut self, log_probs: u16) -> Self {
-        match self.create_completion {
-            Ok(ref mut cc) => {
-                cc.log_probs = Some(log_probs);
-                self
-            }
-            Err(_) => self,
-        }
+        self.create_completion.log_probs = Some(log_probs);
+        self
     }
+
     pub fn echo(mut self, echo: bool) -> Self {
-        match self.create_completion {
-            Ok(ref mut cc) => {
-                cc.echo = echo;
-                self
-            }
-            Err(_) => self,
-        }
+        self.create_completion.echo = echo;
+        self
     }
+
     pub fn stop(mut self, stop: Stop) -> Self {
-        match self.create_completion {
-            Ok(ref mut cc) => {
-                cc.stop = stop;
-                self
-            }
-            Err(_) => self,
-        }
+        self.create_completion.stop = stop;
+        self
     }
 
     pub fn presence_penalty(mut self, presence_penalty: f32) -> Self {
-        match self.create_completion {
-            Ok(ref mut cc) => {
-                if !(-2.0..=2.0).contains(&presence_penalty) {
-                    self.create_completion =
-                        Err("Temperature must be in range [0, 1.0]".to_string());
-                } else {
-                    cc.presence_penalty = Some(presence_penalty);
-                }
-                self
-            }
-            Err(_) => self,
-        }
+        self.create_completion.presence_penalty = Some(presence_penalty);
+        self
     }
+
+    pub fn frequency_penalty(mut self, frequency_penalty: f32) -> Self {
+        self.create_completion.frequency_penalty = Some(frequency_penalty);
+        self
+    }
+
     pub fn best_of(mut self, best_of: u16) -> Self {
-        match self.create_completion {
-            Ok(ref mut cc) => {
-                cc.best_of = Some(best_of);
-                self
-            }
-            Err(_) => self,
-        }
+        self.create_completion.best_of = Some(best_of);
+        self
     }
 
     pub fn logit_bias(mut self, logit_bias: HashMap<String, f32>) -> Self {
-        match self.create_completion {
-            Ok(ref mut cc) => {
-                cc.logit_bias = Some(logit_bias);
-                self
-            }
-            Err(_) => self,
-        }
+        self.create_completion.logit_bias = Some(logit_bias);
+        self
+    }
+
+    pub fn seed(mut self, seed: i64) -> Self {
+        self.create_completion.seed = Some(seed);
+        self
     }
 
     pub fn user(mut self, user: String) -> Self {
-        match self.create_completion {
-            Ok(ref mut cc) => {
-                cc.user = Some(user);
-                self
-            }
-            Err(_) => self,
-        }
+        self.create_completion.user = Some(user);
+        self
     }
 
-    pub fn build(self) -> Result<CreateCompletion, String> {
-        let cc = self.create_completion?;
-        if let (Some(n), Some(best_of)) = (cc.n, cc.best_of) {
-            if n <= best_of {
-                return Err("If both are specified, best_of must be greater than n".to_string());
-            }
-        }
-        Ok(cc)
+    /// Checks every parameter and returns the request, or all of the problems found. see [CreateCompletion::validate]
+    pub fn build(self) -> Result<CreateCompletion, BuildErrors> {
+        self.create_completion.validate()?;
+        Ok(self.create_completion)
     }
 }
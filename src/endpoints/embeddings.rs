@@ -0,0 +1,165 @@
+use crate::endpoints::response::ObjectKind;
+use crate::endpoints::create_completion::NullableOneOrMany;
+use crate::endpoints::Usage;
+use crate::rate_limit::estimate_tokens;
+use crate::{Method, Request};
+use base64::Engine as _;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+
+/// The text(s) to embed. Each input must not exceed the model's max input tokens.
+pub type EmbeddingInput = NullableOneOrMany<String>;
+
+/// The format the API sends the vectors back in. Either way, the response exposes them as `Vec<f32>`;
+/// base64 is just more compact on the wire.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EncodingFormat {
+    Float,
+    Base64,
+}
+
+/// Represents the create embeddings endpoint. see https://platform.openai.com/docs/api-reference/embeddings/create
+#[derive(Serialize, Debug)]
+pub struct CreateEmbedding {
+    /// name of the model to use; e.g. text-embedding-3-small
+    pub model: String,
+    /// A single text or a batch of texts to embed
+    #[serde(skip_serializing_if = "NullableOneOrMany::is_none")]
+    pub input: EmbeddingInput,
+    /// Defaults to float
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding_format: Option<EncodingFormat>,
+    /// The number of dimensions the resulting vectors should have. Only supported by text-embedding-3 and later models.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u32>,
+    /// A unique identifier representing your end-user, which will help OpenAI to monitor and detect abuse.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+impl CreateEmbedding {
+    pub fn new<S: Into<String>>(model: S, input: EmbeddingInput) -> Self {
+        Self {
+            model: model.into(),
+            input,
+            encoding_format: None,
+            dimensions: None,
+            user: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Embedding {
+    /// The index of the input this embedding was generated for
+    pub index: usize,
+    #[serde(deserialize_with = "deserialize_vector")]
+    pub embedding: Vec<f32>,
+}
+
+impl Embedding {
+    /// Cosine similarity between this embedding and another one. see [cosine_similarity]
+    pub fn cosine_similarity(&self, other: &Embedding) -> f32 {
+        cosine_similarity(&self.embedding, &other.embedding)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CreateEmbeddingResponse {
+    pub object: ObjectKind,
+    pub model: String,
+    pub data: Vec<Embedding>,
+    pub usage: Usage,
+}
+
+impl CreateEmbeddingResponse {
+    /// The embedding vectors, in the same order as the inputs
+    pub fn into_vectors(mut self) -> Vec<Vec<f32>> {
+        self.data.sort_by_key(|e| e.index);
+        self.data.into_iter().map(|e| e.embedding).collect()
+    }
+}
+
+impl Request for CreateEmbedding {
+    type Resp = CreateEmbeddingResponse;
+    type Body = Self;
+    const METHOD: Method = Method::POST;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        Cow::from("embeddings")
+    }
+
+    fn azure_deployment_scoped(&self) -> bool {
+        true
+    }
+
+    fn body(&self) -> Option<&Self::Body> {
+        Some(self)
+    }
+
+    fn usage(resp: &Self::Resp) -> Option<&Usage> {
+        Some(&resp.usage)
+    }
+
+    fn estimated_tokens(&self) -> u32 {
+        match &self.input {
+            NullableOneOrMany::None => 0,
+            NullableOneOrMany::One { one } => estimate_tokens(one),
+            NullableOneOrMany::Many { many } => many.iter().map(|s| estimate_tokens(s)).fold(0, u32::saturating_add),
+        }
+    }
+}
+
+/// The vector is either a plain JSON array, or (with [EncodingFormat::Base64]) base64-encoded little-endian f32s
+fn deserialize_vector<'de, D>(deserializer: D) -> Result<Vec<f32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawVector {
+        Floats(Vec<f32>),
+        Base64(String),
+    }
+
+    match RawVector::deserialize(deserializer)? {
+        RawVector::Floats(floats) => Ok(floats),
+        RawVector::Base64(encoded) => decode_base64_vector(&encoded).map_err(D::Error::custom),
+    }
+}
+
+/// Decodes a vector sent with [EncodingFormat::Base64]
+pub fn decode_base64_vector(encoded: &str) -> Result<Vec<f32>, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("invalid base64 embedding: {}", e))?;
+    if bytes.len() % 4 != 0 {
+        return Err(format!(
+            "base64 embedding is {} bytes, which is not a whole number of f32s",
+            bytes.len()
+        ));
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect())
+}
+
+/// Dot product of two vectors. Extra elements in the longer vector are ignored.
+pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Cosine similarity of two vectors, in [-1, 1]. Returns 0 if either vector is all zeroes.
+///
+/// OpenAI embeddings are normalized to length 1, so for those this is the same as [dot_product].
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let norms = dot_product(a, a).sqrt() * dot_product(b, b).sqrt();
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot_product(a, b) / norms
+    }
+}
@@ -0,0 +1,264 @@
+//! The files endpoints, for uploading and managing files used by other endpoints (fine-tuning, batches,
+//! assistants). see https://platform.openai.com/docs/api-reference/files
+
+use crate::endpoints::path_segment;
+use crate::endpoints::response::{string_enum, ObjectKind};
+use crate::{Error, Method, Request};
+use bytes::Bytes;
+use futures_util::Stream;
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::pin::Pin;
+use std::sync::{Mutex, PoisonError};
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+
+string_enum! {
+    /// What a file is for, which determines what it can be used with and the formats allowed
+    pub enum FilePurpose {
+        Assistants => "assistants",
+        AssistantsOutput => "assistants_output",
+        Batch => "batch",
+        BatchOutput => "batch_output",
+        FineTune => "fine-tune",
+        FineTuneResults => "fine-tune-results",
+        Vision => "vision",
+        UserData => "user_data",
+        Evals => "evals",
+    }
+}
+
+/// A file stored by the API
+#[derive(Deserialize, Debug, Clone)]
+pub struct FileObject {
+    pub id: String,
+    pub object: ObjectKind,
+    /// Size of the file, in bytes
+    pub bytes: u64,
+    /// Unix timestamp (in seconds) when the file was created
+    pub created_at: u64,
+    pub filename: String,
+    pub purpose: FilePurpose,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ListFilesResponse {
+    pub object: ObjectKind,
+    pub data: Vec<FileObject>,
+    /// Whether there are more files after these, to fetch with [ListFiles::after]
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeleteFileResponse {
+    /// The id of the file that was deleted
+    pub id: String,
+    pub object: ObjectKind,
+    pub deleted: bool,
+}
+
+/// Where the contents of an upload come from
+enum FileSource {
+    /// Shared rather than copied by each attempt
+    Bytes(Bytes),
+    /// A reader can only be read once, so it is taken by the first attempt to send the upload
+    Reader {
+        reader: Mutex<Option<Box<dyn AsyncRead + Send + Unpin>>>,
+        length: Option<u64>,
+    },
+}
+
+/// Uploads a file. Individual files can be up to 512 MB.
+///
+/// An upload made [from a reader](Self::from_reader) isn't retried, since the reader is used up by the first
+/// attempt; if that fails, its error is returned as it is.
+pub struct UploadFile {
+    pub purpose: FilePurpose,
+    pub filename: String,
+    source: FileSource,
+}
+
+impl UploadFile {
+    pub fn from_bytes<S: Into<String>, B: Into<Vec<u8>>>(purpose: FilePurpose, filename: S, bytes: B) -> Self {
+        Self {
+            purpose,
+            filename: filename.into(),
+            source: FileSource::Bytes(Bytes::from(bytes.into())),
+        }
+    }
+
+    /// Streams the file from `reader`, e.g. a `tokio::fs::File`, rather than holding it all in memory. Giving
+    /// the `length`, if known, lets it be sent with a `Content-Length`.
+    pub fn from_reader<S, R>(purpose: FilePurpose, filename: S, reader: R, length: Option<u64>) -> Self
+    where
+        S: Into<String>,
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        Self {
+            purpose,
+            filename: filename.into(),
+            source: FileSource::Reader {
+                reader: Mutex::new(Some(Box::new(reader))),
+                length,
+            },
+        }
+    }
+}
+
+/// Reads a reader in chunks, as a body that can be streamed. reqwest needs the body to be `Sync`, which the
+/// reader doesn't have to be: holding it in a [Mutex], which is never locked, makes up for that.
+struct ReaderStream {
+    reader: Mutex<Box<dyn AsyncRead + Send + Unpin>>,
+    chunk: Vec<u8>,
+}
+
+impl ReaderStream {
+    const CHUNK_SIZE: usize = 64 * 1024;
+
+    fn body(reader: Box<dyn AsyncRead + Send + Unpin>) -> reqwest::Body {
+        reqwest::Body::wrap_stream(ReaderStream {
+            reader: Mutex::new(reader),
+            chunk: vec![0; Self::CHUNK_SIZE],
+        })
+    }
+}
+
+impl Stream for ReaderStream {
+    type Item = std::io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let reader = this.reader.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let mut buf = ReadBuf::new(&mut this.chunk);
+        match Pin::new(reader).poll_read(cx, &mut buf) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Err(e)) => Poll::Ready(Some(Err(e))),
+            Poll::Ready(Ok(())) if buf.filled().is_empty() => Poll::Ready(None),
+            Poll::Ready(Ok(())) => {
+                let read = buf.filled().len();
+                let mut chunk = std::mem::replace(&mut this.chunk, vec![0; Self::CHUNK_SIZE]);
+                chunk.truncate(read);
+                Poll::Ready(Some(Ok(chunk)))
+            }
+        }
+    }
+}
+
+impl Request for UploadFile {
+    type Resp = FileObject;
+    type Body = ();
+    const METHOD: Method = Method::POST;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        Cow::from("files")
+    }
+
+    fn replayable(&self) -> bool {
+        matches!(self.source, FileSource::Bytes(_))
+    }
+
+    fn form(&self) -> Result<Option<Form>, Error> {
+        let part = match &self.source {
+            FileSource::Bytes(bytes) => Part::stream_with_length(bytes.clone(), bytes.len() as u64),
+            FileSource::Reader { reader, length } => {
+                let reader = reader.lock().unwrap().take().ok_or_else(|| Error::ConfigError {
+                    err: format!("the reader for {} was already used by an earlier attempt", self.filename),
+                })?;
+                match length {
+                    Some(length) => Part::stream_with_length(ReaderStream::body(reader), *length),
+                    None => Part::stream(ReaderStream::body(reader)),
+                }
+            }
+        };
+        let form = Form::new()
+            .text("purpose", self.purpose.to_string())
+            .part("file", part.file_name(self.filename.clone()));
+        Ok(Some(form))
+    }
+}
+
+/// Lists the files belonging to the organization, newest first
+#[derive(Default)]
+pub struct ListFiles {
+    /// Only list files with this purpose
+    pub purpose: Option<FilePurpose>,
+    /// How many files to return, between 1 and 10,000. Defaults to 10,000.
+    pub limit: Option<u32>,
+    /// A file id to start listing after, for pagination
+    pub after: Option<String>,
+}
+
+impl Request for ListFiles {
+    type Resp = ListFilesResponse;
+    type Body = ();
+    const METHOD: Method = Method::GET;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        if let Some(purpose) = &self.purpose {
+            query.append_pair("purpose", purpose.as_str());
+        }
+        if let Some(limit) = self.limit {
+            query.append_pair("limit", &limit.to_string());
+        }
+        if let Some(after) = &self.after {
+            query.append_pair("after", after);
+        }
+        let query = query.finish();
+        if query.is_empty() {
+            Cow::from("files")
+        } else {
+            Cow::from(format!("files?{}", query))
+        }
+    }
+}
+
+/// Retrieves a file's details by id. see [RetrieveFileContent] for its contents
+pub struct RetrieveFile {
+    pub file_id: String,
+}
+
+impl Request for RetrieveFile {
+    type Resp = FileObject;
+    type Body = ();
+    const METHOD: Method = Method::GET;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        Cow::from(format!("files/{}", path_segment(&self.file_id)))
+    }
+}
+
+pub struct DeleteFile {
+    pub file_id: String,
+}
+
+impl Request for DeleteFile {
+    type Resp = DeleteFileResponse;
+    type Body = ();
+    const METHOD: Method = Method::DELETE;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        Cow::from(format!("files/{}", path_segment(&self.file_id)))
+    }
+}
+
+/// Downloads a file's contents, as they were uploaded
+pub struct RetrieveFileContent {
+    pub file_id: String,
+}
+
+impl Request for RetrieveFileContent {
+    type Resp = Vec<u8>;
+    type Body = ();
+    const METHOD: Method = Method::GET;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        Cow::from(format!("files/{}/content", path_segment(&self.file_id)))
+    }
+
+    fn decode(body: &[u8]) -> Result<Self::Resp, Error> {
+        Ok(body.to_vec())
+    }
+}
@@ -0,0 +1,175 @@
+//! Tool definitions and response formats derived from Rust types, via `schemars`. Only available with the
+//! `schemars` feature.
+
+use crate::endpoints::{FunctionDefinition, JsonSchemaFormat, Tool, ToolCall};
+use crate::Error;
+use schemars::generate::SchemaSettings;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::marker::PhantomData;
+
+/// The string formats strict mode understands
+const SUPPORTED_FORMATS: &[&str] = &[
+    "date-time", "time", "date", "duration", "email", "hostname", "ipv4", "ipv6", "uuid",
+];
+
+/// A strict-mode JSON Schema for `T`, as far as `T` can be expressed in one. see
+/// [FunctionDefinition::from_type] for how it differs from what `schemars` generates.
+pub fn json_schema_for<T: JsonSchema>() -> Value {
+    schema_for::<T>().0
+}
+
+/// `T`'s schema, and whether it can be used in strict mode
+fn schema_for<T: JsonSchema>() -> (Value, bool) {
+    let generator = SchemaSettings::draft2020_12()
+        .with(|s| {
+            s.meta_schema = None;
+            s.inline_subschemas = true;
+        })
+        .into_generator();
+    let mut schema = generator.into_root_schema_for::<T>().to_value();
+    let strict = make_strict(&mut schema);
+    (schema, strict)
+}
+
+/// Splits the top-level description off a schema, dropping its title
+fn split_description(mut schema: Value) -> (Value, Option<String>) {
+    let description = schema.as_object_mut().and_then(|o| {
+        o.remove("title");
+        o.remove("description")
+    });
+    (schema, description.and_then(|d| d.as_str().map(String::from)))
+}
+
+/// Rewrites `schema` into the strict-mode subset, returning false if it contains something strict mode can't
+/// express
+fn make_strict(schema: &mut Value) -> bool {
+    let object = match schema.as_object_mut() {
+        Some(object) => object,
+        None => return true,
+    };
+    let mut strict = true;
+    if let Some(Value::Object(properties)) = object.get("properties") {
+        let required = properties.keys().cloned().map(Value::String).collect();
+        object.insert("required".to_string(), Value::Array(required));
+        object.insert("additionalProperties".to_string(), Value::Bool(false));
+    } else if matches!(object.get("additionalProperties"), Some(Value::Object(_)) | Some(Value::Bool(true))) {
+        // a map, whose keys aren't known up front
+        strict = false;
+    }
+    if matches!(object.get("format"), Some(Value::String(f)) if !SUPPORTED_FORMATS.contains(&f.as_str())) {
+        object.remove("format");
+    }
+    object.remove("default");
+    if let Some(one_of) = object.remove("oneOf") {
+        object.insert("anyOf".to_string(), one_of);
+    }
+    for (key, value) in object.iter_mut() {
+        let subschemas: Vec<&mut Value> = match (key.as_str(), value) {
+            ("properties", Value::Object(schemas)) | ("$defs", Value::Object(schemas)) => schemas.values_mut().collect(),
+            ("anyOf", Value::Array(schemas)) | ("allOf", Value::Array(schemas)) | ("prefixItems", Value::Array(schemas)) => {
+                schemas.iter_mut().collect()
+            }
+            ("items", schema) | ("additionalProperties", schema) | ("not", schema) => vec![schema],
+            _ => vec![],
+        };
+        for schema in subschemas {
+            strict &= make_strict(schema);
+        }
+    }
+    strict
+}
+
+impl FunctionDefinition {
+    /// A function whose parameters are `T`, described by `T`'s doc comment.
+    ///
+    /// The schema is rewritten into the subset of JSON Schema that strict mode accepts: every object lists all of
+    /// its properties as required and disallows others (optional fields become nullable instead), `oneOf` becomes
+    /// `anyOf`, and `default`s and unsupported `format`s are dropped.
+    ///
+    /// Maps, e.g. `HashMap` fields, can't be expressed in strict mode, since it needs every property named up
+    /// front. If `T` contains one, `strict` is set to false: the API accepts the schema, but doesn't guarantee
+    /// the model's arguments follow it.
+    pub fn from_type<T: JsonSchema, S: Into<String>>(name: S) -> Self {
+        let (schema, strict) = schema_for::<T>();
+        let (parameters, description) = split_description(schema);
+        Self {
+            name: name.into(),
+            description,
+            parameters: Some(parameters),
+            strict: Some(strict),
+        }
+    }
+}
+
+impl JsonSchemaFormat {
+    /// A format for output that deserializes into `T`, described by `T`'s doc comment.
+    ///
+    /// The schema is adapted for strict mode the same way as [FunctionDefinition::from_type]'s, e.g. `oneOf`
+    /// becomes `anyOf` and `default`s are dropped. If `T` contains a map, `strict` is set to false, so the API
+    /// doesn't guarantee the reply follows the schema.
+    pub fn from_type<T: JsonSchema, S: Into<String>>(name: S) -> Self {
+        let (schema, strict) = schema_for::<T>();
+        let (schema, description) = split_description(schema);
+        Self {
+            name: name.into(),
+            description,
+            schema,
+            strict: Some(strict),
+        }
+    }
+}
+
+/// A function tool whose arguments are `T`, for defining it and then picking out and parsing calls to it
+pub struct TypedTool<T> {
+    definition: FunctionDefinition,
+    args: PhantomData<fn() -> T>,
+}
+
+impl<T: JsonSchema + DeserializeOwned> TypedTool<T> {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            definition: FunctionDefinition::from_type::<T, S>(name),
+            args: PhantomData,
+        }
+    }
+
+    /// Overrides the description taken from `T`'s doc comment
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.definition.description = Some(description.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.definition.name
+    }
+
+    pub fn definition(&self) -> &FunctionDefinition {
+        &self.definition
+    }
+
+    /// The tool, to pass to [CreateChatCompletionBuilder::tool](crate::endpoints::CreateChatCompletionBuilder::tool)
+    pub fn tool(&self) -> Tool {
+        Tool::function(self.definition.clone())
+    }
+
+    /// The arguments of `call`, or `None` if it's a call to a different tool. Arguments that don't fit `T`
+    /// are an [Error::SchemaMismatch].
+    pub fn parse(&self, call: &ToolCall) -> Option<Result<T, Error>> {
+        if call.function.name == self.definition.name {
+            Some(call.parse_arguments())
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Clone for TypedTool<T> {
+    fn clone(&self) -> Self {
+        Self {
+            definition: self.definition.clone(),
+            args: PhantomData,
+        }
+    }
+}
@@ -1,31 +1,32 @@
+use crate::endpoints::response::ObjectKind;
 use crate::{Request, Method};
 use std::borrow::Cow;
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
 pub struct Engine {
-    id: String,
-    object: String,
-    owner: String,
-    ready: bool,
+    pub id: String,
+    pub object: ObjectKind,
+    pub owner: String,
+    pub ready: bool,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct ListEnginesResponse {
-    data: Vec<Engine>,
-    object: String,
+    pub data: Vec<Engine>,
+    pub object: ObjectKind,
 }
 
-#[deprecated(since="0.1.1", note="Engines deprecated in favour of Models")]
+#[deprecated(since="0.1.1", note="Engines deprecated in favour of Models; use ListModels")]
 pub struct ListEngines {}
 
-
+#[allow(deprecated)]
 impl Request for ListEngines {
     type Resp = ListEnginesResponse;
     type Body = ();
     const METHOD: Method = Method::GET;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         Cow::from("engines")
     }
 }
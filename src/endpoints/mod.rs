@@ -1,7 +1,64 @@
 mod list_engines;
 mod create_completion;
+mod create_chat_completion;
+mod embeddings;
+mod files;
+#[cfg(feature = "schemars")]
+mod json_schema;
+mod models;
 mod moderation;
+mod response;
+mod response_format;
+mod tools;
+mod usage;
+mod validation;
 
+#[allow(deprecated)]
 pub use list_engines::ListEngines;
-pub use create_completion::{CreateCompletion, CreateCompletionBuilder, CreateCompletionResponse, Prompt, Stop};
-pub use moderation::{Categories, Moderations, ModerationsResponse, ModerationsModel, ModerationsResult};
\ No newline at end of file
+pub use create_completion::{
+    Choice, CreateCompletion, CreateCompletionBuilder, CreateCompletionResponse, LogProbs, NullableOneOrMany, Prompt, Stop,
+    StreamOptions,
+};
+pub use create_chat_completion::{
+    ChatChoice, ChatChoiceDelta, ChatContent, ChatMessage, ChatMessageDelta, ContentPart, CreateChatCompletion,
+    CreateChatCompletionBuilder, CreateChatCompletionChunk, CreateChatCompletionResponse, ImageDetail, ImageUrl, Role,
+};
+pub use models::{DeleteModel, DeleteModelResponse, ListModels, ListModelsResponse, Model, RetrieveModel};
+pub use embeddings::{
+    cosine_similarity, decode_base64_vector, dot_product, CreateEmbedding, CreateEmbeddingResponse, Embedding,
+    EmbeddingInput, EncodingFormat,
+};
+pub use files::{
+    DeleteFile, DeleteFileResponse, FileObject, FilePurpose, ListFiles, ListFilesResponse, RetrieveFile, RetrieveFileContent,
+    UploadFile,
+};
+pub use moderation::{Categories, Moderations, ModerationsResponse, ModerationsModel, ModerationsResult};
+pub use response::{FinishReason, ObjectKind};
+pub use tools::{FunctionCall, FunctionCallDelta, FunctionDefinition, Tool, ToolCall, ToolCallDelta, ToolChoice};
+pub use usage::Usage;
+#[cfg(feature = "schemars")]
+pub use json_schema::{json_schema_for, TypedTool};
+pub use response_format::{JsonSchemaFormat, ResponseFormat};
+pub use validation::{max_output_tokens, BuildError, BuildErrors};
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+/// What has to be escaped for a value to stay within one segment of a URL's path
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Escapes an id given by the caller so it can be put in an endpoint's path as a single segment, and can't
+/// point the request at some other path or add a query
+pub(crate) fn path_segment(id: &str) -> impl std::fmt::Display + '_ {
+    utf8_percent_encode(id, PATH_SEGMENT)
+}
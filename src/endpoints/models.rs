@@ -0,0 +1,74 @@
+use crate::endpoints::path_segment;
+use crate::endpoints::response::ObjectKind;
+use crate::{Method, Request};
+use serde::Deserialize;
+use std::borrow::Cow;
+
+/// A model that can be used with the API. see https://platform.openai.com/docs/api-reference/models/object
+#[derive(Deserialize, Debug, Clone)]
+pub struct Model {
+    /// The model identifier, which can be referenced in the API endpoints
+    pub id: String,
+    pub object: ObjectKind,
+    /// Unix timestamp (in seconds) when the model was created
+    pub created: u64,
+    /// The organization that owns the model
+    pub owned_by: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ListModelsResponse {
+    pub data: Vec<Model>,
+    pub object: ObjectKind,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeleteModelResponse {
+    /// The id of the model that was deleted
+    pub id: String,
+    pub object: ObjectKind,
+    pub deleted: bool,
+}
+
+/// Lists the currently available models
+pub struct ListModels {}
+
+impl Request for ListModels {
+    type Resp = ListModelsResponse;
+    type Body = ();
+    const METHOD: Method = Method::GET;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        Cow::from("models")
+    }
+}
+
+/// Retrieves a single model by id
+pub struct RetrieveModel {
+    pub id: String,
+}
+
+impl Request for RetrieveModel {
+    type Resp = Model;
+    type Body = ();
+    const METHOD: Method = Method::GET;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        Cow::from(format!("models/{}", path_segment(&self.id)))
+    }
+}
+
+/// Deletes a fine-tuned model. You must have the Owner role in your organization to delete a model.
+pub struct DeleteModel {
+    pub id: String,
+}
+
+impl Request for DeleteModel {
+    type Resp = DeleteModelResponse;
+    type Body = ();
+    const METHOD: Method = Method::DELETE;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        Cow::from(format!("models/{}", path_segment(&self.id)))
+    }
+}
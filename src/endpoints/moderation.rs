@@ -1,8 +1,8 @@
 use std::borrow::Cow;
-use serde::{Serialize, Serializer, Deserialize, Deserializer};
-use serde::de::DeserializeOwned;
+use serde::{Serialize, Serializer, Deserialize};
 use crate::{Method, Request};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ModerationsModel {
     TextModerationStable,
     TextModerationLatest,
@@ -34,7 +34,7 @@ pub struct Moderations {
 }
 
 #[non_exhaustive]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
 /// A list of all the categories that the model potentially classifies content into
 pub struct Categories<T> {
     pub hate: T,
@@ -51,7 +51,35 @@ pub struct Categories<T> {
 }
 
 
-#[derive(Deserialize, Debug)]
+impl<T: Copy> Categories<T> {
+    /// The same value for every category
+    pub(crate) fn splat(value: T) -> Self {
+        Self {
+            hate: value,
+            hate_threatening: value,
+            self_harm: value,
+            sexual: value,
+            sexual_minors: value,
+            violence: value,
+            violence_graphic: value,
+        }
+    }
+
+    /// Combines two sets of values category by category
+    pub(crate) fn zip_with<F: Fn(T, T) -> T>(&self, other: &Self, f: F) -> Self {
+        Self {
+            hate: f(self.hate, other.hate),
+            hate_threatening: f(self.hate_threatening, other.hate_threatening),
+            self_harm: f(self.self_harm, other.self_harm),
+            sexual: f(self.sexual, other.sexual),
+            sexual_minors: f(self.sexual_minors, other.sexual_minors),
+            violence: f(self.violence, other.violence),
+            violence_graphic: f(self.violence_graphic, other.violence_graphic),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
 pub struct ModerationsResult {
     /// each field holds a 1 if that category was flagged for moderation and a 0 otherwise
     pub categories: Categories<bool>,
@@ -78,11 +106,11 @@ impl Request for Moderations {
     type Body = Self;
     const METHOD: Method = Method::POST;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         Cow::from("moderations")
     }
 
     fn body(&self) -> Option<&Self::Body> {
-        Some(&self)
+        Some(self)
     }
 }
\ No newline at end of file
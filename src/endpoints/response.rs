@@ -0,0 +1,90 @@
+//! String-valued fields that are shared by several response types. Each has a fallback variant so a value the
+//! API adds later still deserializes.
+
+/// Defines a `#[non_exhaustive]` enum over known string values, plus an `Unknown(String)` variant that any other
+/// value deserializes to
+macro_rules! string_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident => $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// A value this version of the crate doesn't know about
+            Unknown(String),
+        }
+
+        impl $name {
+            /// The value as the API sends it
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $($value => $name::$variant,)*
+                    other => $name::Unknown(other.to_string()),
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+                Ok($name::from(value.as_str()))
+            }
+        }
+    };
+}
+
+pub(crate) use string_enum;
+
+string_enum! {
+    /// Why the model stopped generating a choice
+    pub enum FinishReason {
+        /// It reached a natural stopping point or one of the `stop` sequences
+        Stop => "stop",
+        /// It reached `max_tokens`, or the model's context length, so the output is cut off
+        Length => "length",
+        /// Content was omitted because it was flagged by the content filters
+        ContentFilter => "content_filter",
+        /// It called one or more tools
+        ToolCalls => "tool_calls",
+    }
+}
+
+string_enum! {
+    /// The `object` field of a response, naming what kind of object it is
+    pub enum ObjectKind {
+        TextCompletion => "text_completion",
+        ChatCompletion => "chat.completion",
+        ChatCompletionChunk => "chat.completion.chunk",
+        Embedding => "embedding",
+        Engine => "engine",
+        File => "file",
+        Model => "model",
+        List => "list",
+    }
+}
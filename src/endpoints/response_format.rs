@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The format the model's reply should take. see https://platform.openai.com/docs/api-reference/chat/create#chat-create-response_format
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Plain text, the default
+    Text,
+    /// Any valid JSON. The messages must also ask for JSON, or the API rejects the request.
+    JsonObject,
+    /// JSON following a schema; with `strict` set, the model is guaranteed to follow it
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+impl From<JsonSchemaFormat> for ResponseFormat {
+    fn from(json_schema: JsonSchemaFormat) -> Self {
+        ResponseFormat::JsonSchema { json_schema }
+    }
+}
+
+/// A JSON Schema that the model's output should follow. see https://platform.openai.com/docs/guides/structured-outputs
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JsonSchemaFormat {
+    /// Must be a-z, A-Z, 0-9, underscores and dashes, at most 64 characters
+    pub name: String,
+    /// What the output is for, used by the model to decide how to respond
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub schema: Value,
+    /// Whether the model must follow `schema` exactly. This restricts which schemas are allowed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+impl JsonSchemaFormat {
+    pub fn new<S: Into<String>>(name: S, schema: Value) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            schema,
+            strict: None,
+        }
+    }
+
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = Some(strict);
+        self
+    }
+}
@@ -0,0 +1,158 @@
+//! Tools (functions) that the model can call from a chat completion. see https://platform.openai.com/docs/guides/function-calling
+
+use crate::Error;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+/// A tool the model may call. Functions are currently the only kind.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Tool {
+    Function { function: FunctionDefinition },
+}
+
+impl Tool {
+    pub fn function(function: FunctionDefinition) -> Self {
+        Tool::Function { function }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Tool::Function { function } => &function.name,
+        }
+    }
+}
+
+/// Describes a function to the model
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FunctionDefinition {
+    /// Must be a-z, A-Z, 0-9, underscores and dashes, at most 64 characters
+    pub name: String,
+    /// What the function does, used by the model to choose when and how to call it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The arguments the function takes, as a JSON Schema object. Omitting it means an empty parameter list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<Value>,
+    /// Whether the model must follow `parameters` exactly. This restricts which schemas are allowed;
+    /// see https://platform.openai.com/docs/guides/structured-outputs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+impl FunctionDefinition {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            parameters: None,
+            strict: None,
+        }
+    }
+
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn parameters(mut self, parameters: Value) -> Self {
+        self.parameters = Some(parameters);
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = Some(strict);
+        self
+    }
+}
+
+/// Whether, and which, tools the model should call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The model decides whether to call tools. The default when tools are given.
+    Auto,
+    /// The model won't call any tool and replies with a message instead
+    None,
+    /// The model must call one or more tools
+    Required,
+    /// The model must call the named function
+    Function(String),
+}
+
+impl Serialize for ToolChoice {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ToolChoice::Auto => serializer.serialize_str("auto"),
+            ToolChoice::None => serializer.serialize_str("none"),
+            ToolChoice::Required => serializer.serialize_str("required"),
+            ToolChoice::Function(name) => {
+                #[derive(Serialize)]
+                struct Name<'a> {
+                    name: &'a str,
+                }
+                #[derive(Serialize)]
+                #[serde(tag = "type", rename = "function")]
+                struct Specific<'a> {
+                    function: Name<'a>,
+                }
+                Specific {
+                    function: Name { name },
+                }
+                .serialize(serializer)
+            }
+        }
+    }
+}
+
+/// A call the model made to one of the request's tools, found in [ChatMessage::Assistant](crate::endpoints::ChatMessage::Assistant)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename = "function")]
+pub struct ToolCall {
+    /// Identifies the call; send it back as the `tool_call_id` of the [ChatMessage::tool](crate::endpoints::ChatMessage::tool)
+    /// message carrying the result
+    pub id: String,
+    pub function: FunctionCall,
+}
+
+impl ToolCall {
+    /// see [FunctionCall::parse_arguments]
+    pub fn parse_arguments<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        self.function.parse_arguments()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FunctionCall {
+    pub name: String,
+    /// The arguments as a string of JSON, as generated by the model. It isn't guaranteed to be valid JSON,
+    /// or to match the function's parameters, unless the function is `strict`.
+    pub arguments: String,
+}
+
+impl FunctionCall {
+    /// Deserializes the arguments into the type the function takes, failing with [Error::SchemaMismatch]
+    /// if they don't fit it
+    pub fn parse_arguments<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        serde_json::from_str(&self.arguments).map_err(|e| Error::SchemaMismatch {
+            name: self.name.clone(),
+            err: e.to_string(),
+        })
+    }
+}
+
+/// The part of a [ToolCall] that arrived in a single streamed event. The first part of each call carries its
+/// id and function name; the arguments arrive in pieces to be concatenated.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ToolCallDelta {
+    /// Which of the message's tool calls this is part of
+    pub index: usize,
+    pub id: Option<String>,
+    pub function: Option<FunctionCallDelta>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FunctionCallDelta {
+    pub name: Option<String>,
+    pub arguments: Option<String>,
+}
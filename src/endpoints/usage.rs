@@ -0,0 +1,13 @@
+use serde::Deserialize;
+
+/// Token counts for a single request, as reported by the API
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Number of tokens in the prompt
+    pub prompt_tokens: u32,
+    /// Number of tokens generated. Endpoints that don't generate anything (e.g. embeddings) leave this at 0
+    #[serde(default)]
+    pub completion_tokens: u32,
+    /// Total number of tokens used by the request (prompt + completion)
+    pub total_tokens: u32,
+}
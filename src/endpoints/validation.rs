@@ -0,0 +1,169 @@
+//! Client-side checks of request parameters, so mistakes surface when a request is built rather than as a
+//! 400 from the API
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// A single problem with a request's parameters
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum BuildError {
+    /// A numeric parameter is outside the range the API accepts
+    OutOfRange {
+        field: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    /// `max_tokens` is more than the model can generate in one response
+    MaxTokensExceeded { max_tokens: u32, limit: u32, model: String },
+    /// `best_of` was set, but not to more than `n`
+    BestOfNotGreaterThanN { best_of: u16, n: u16 },
+    /// A list parameter has more entries than the API accepts
+    TooMany { field: &'static str, count: usize, max: usize },
+    /// The parameter can't be used on a streamed request
+    IncompatibleWithStream { field: &'static str },
+    /// The parameter only applies to streamed requests, but `stream` isn't set
+    RequiresStream { field: &'static str },
+    /// A required parameter wasn't given
+    Missing { field: &'static str },
+    /// `tool_choice` names a function that isn't among the request's tools
+    UnknownTool { name: String },
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::OutOfRange { field, value, min, max } => {
+                write!(f, "{} must be in range [{}, {}], got {}", field, min, max, value)
+            }
+            BuildError::MaxTokensExceeded { max_tokens, limit, model } => {
+                write!(f, "max_tokens cannot exceed {} for {}, got {}", limit, model, max_tokens)
+            }
+            BuildError::BestOfNotGreaterThanN { best_of, n } => {
+                write!(f, "best_of must be greater than n, got best_of {} and n {}", best_of, n)
+            }
+            BuildError::TooMany { field, count, max } => {
+                write!(f, "{} can have at most {} entries, got {}", field, max, count)
+            }
+            BuildError::IncompatibleWithStream { field } => {
+                write!(f, "{} cannot be used when streaming", field)
+            }
+            BuildError::RequiresStream { field } => {
+                write!(f, "{} can only be set when streaming", field)
+            }
+            BuildError::Missing { field } => {
+                write!(f, "{} is required", field)
+            }
+            BuildError::UnknownTool { name } => {
+                write!(f, "tool_choice names {}, which is not one of the tools", name)
+            }
+        }
+    }
+}
+
+/// Every problem found with a request, returned by the builders' `build`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildErrors(pub Vec<BuildError>);
+
+impl BuildErrors {
+    pub fn iter(&self) -> std::slice::Iter<'_, BuildError> {
+        self.0.iter()
+    }
+
+    /// Whether any of the errors concern `field`
+    pub fn concerns(&self, field: &str) -> bool {
+        self.0.iter().any(|e| match e {
+            BuildError::OutOfRange { field: f, .. }
+            | BuildError::TooMany { field: f, .. }
+            | BuildError::IncompatibleWithStream { field: f }
+            | BuildError::RequiresStream { field: f }
+            | BuildError::Missing { field: f } => *f == field,
+            BuildError::MaxTokensExceeded { .. } => field == "max_tokens",
+            BuildError::BestOfNotGreaterThanN { .. } => field == "best_of" || field == "n",
+            BuildError::UnknownTool { .. } => field == "tool_choice",
+        })
+    }
+}
+
+impl Display for BuildErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let messages: Vec<String> = self.0.iter().map(|e| e.to_string()).collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+/// The most tokens `model` can generate in a single response, if known. Fine-tuned models (`ft:{base}:...`)
+/// share the limit of their base model.
+pub fn max_output_tokens(model: &str) -> Option<u32> {
+    // longest prefixes first, so e.g. gpt-4o isn't treated as gpt-4
+    const LIMITS: &[(&str, u32)] = &[
+        ("gpt-3.5-turbo-instruct", 4096),
+        ("gpt-3.5-turbo", 4096),
+        ("gpt-4o-mini", 16384),
+        ("gpt-4o", 16384),
+        ("gpt-4.1", 32768),
+        ("gpt-4-turbo", 4096),
+        ("gpt-4", 8192),
+        ("davinci-002", 16384),
+        ("babbage-002", 16384),
+        ("text-davinci", 4096),
+    ];
+    let base = model.strip_prefix("ft:").unwrap_or(model);
+    LIMITS
+        .iter()
+        .find(|(prefix, _)| base.starts_with(prefix))
+        .map(|(_, limit)| *limit)
+}
+
+/// Accumulates [BuildError]s while checking a request
+#[derive(Default)]
+pub(crate) struct Validator {
+    errors: Vec<BuildError>,
+}
+
+impl Validator {
+    pub(crate) fn range<T: Into<f64> + Copy>(&mut self, field: &'static str, value: Option<T>, min: f64, max: f64) {
+        if let Some(value) = value.map(Into::into) {
+            if !(min..=max).contains(&value) {
+                self.errors.push(BuildError::OutOfRange { field, value, min, max });
+            }
+        }
+    }
+
+    pub(crate) fn max_tokens(&mut self, model: &str, max_tokens: Option<u16>) {
+        if let (Some(max_tokens), Some(limit)) = (max_tokens, max_output_tokens(model)) {
+            if u32::from(max_tokens) > limit {
+                self.errors.push(BuildError::MaxTokensExceeded {
+                    max_tokens: max_tokens.into(),
+                    limit,
+                    model: model.to_string(),
+                });
+            }
+        }
+    }
+
+    pub(crate) fn count(&mut self, field: &'static str, count: usize, max: usize) {
+        if count > max {
+            self.errors.push(BuildError::TooMany { field, count, max });
+        }
+    }
+
+    pub(crate) fn logit_bias(&mut self, logit_bias: Option<&HashMap<String, f32>>) {
+        for bias in logit_bias.into_iter().flat_map(|b| b.values()) {
+            self.range("logit_bias", Some(*bias), -100.0, 100.0);
+        }
+    }
+
+    pub(crate) fn push(&mut self, error: BuildError) {
+        self.errors.push(error);
+    }
+
+    pub(crate) fn finish(self) -> Result<(), BuildErrors> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(BuildErrors(self.errors))
+        }
+    }
+}
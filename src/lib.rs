@@ -1,20 +1,49 @@
 pub mod endpoints;
+mod backend;
+mod cassette;
+mod client_builder;
 mod content_filter;
+mod moderate;
+mod rate_limit;
+mod response;
+mod retry;
+mod sse;
+mod telemetry;
+mod tool_runner;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod transport;
+mod usage_tracker;
 
 use aliri_braid::braid;
+use futures_util::stream::{self, Stream, StreamExt};
 use std::borrow::Cow;
+use reqwest::header::HeaderMap;
 use reqwest::Client as ReqwestClient;
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+use telemetry::RequestSpan;
+use transport::{Transport, TransportError};
 
+pub use backend::{AzureDeployment, DEFAULT_AZURE_API_VERSION};
+use backend::Backend;
+pub use cassette::{Cassette, CassetteMode, Interaction, RecordedRequest, RecordedResponse};
+pub use client_builder::OpenAIClientBuilder;
+#[allow(deprecated)]
 pub use content_filter::{filter_content, FilterLabel};
+pub use moderate::{moderate_text, moderate_text_with, FlaggedSpan, ModerationOptions, ModerationVerdict};
+pub use rate_limit::RateLimit;
+use rate_limit::RateLimiter;
+pub use response::{Response, ResponseMeta};
+pub use retry::{Clock, RetryPolicy, TokioClock};
+pub use tool_runner::{Step, ToolOutcome, ToolRun, ToolRunner};
+pub use usage_tracker::UsageTotals;
+use usage_tracker::UsageTracker;
 
-const BASE_URL: &str = "https://api.openai.com/v1";
-
-fn build_url(endpoint: Cow<str>) -> String {
-    format!("{}/{}", BASE_URL, endpoint)
-}
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
 
 #[braid]
 pub struct ApiKey;
@@ -22,16 +51,102 @@ pub struct ApiKey;
 pub enum Method {
     GET,
     POST,
+    DELETE,
+}
+
+/// The error object OpenAI returns in the body of failed requests, i.e. the contents of
+/// `{"error": {"message": ..., "type": ..., "param": ..., "code": ...}}`
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    /// Human-readable description of the error
+    pub message: String,
+    /// Broad category of the error, e.g. `invalid_request_error`
+    #[serde(rename = "type", default)]
+    pub kind: Option<String>,
+    /// The request parameter the error relates to, if any
+    #[serde(default)]
+    pub param: Option<String>,
+    /// Machine-readable error code, e.g. `rate_limit_exceeded`. Some errors give a number instead, which is kept
+    /// as its decimal string.
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub code: Option<String>,
+}
+
+fn string_or_number<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Code {
+        String(String),
+        Number(serde_json::Number),
+    }
+    Ok(Option::<Code>::deserialize(deserializer)?.map(|code| match code {
+        Code::String(code) => code,
+        Code::Number(code) => code.to_string(),
+    }))
+}
+
+#[derive(Deserialize)]
+struct ApiErrorEnvelope {
+    error: ApiError,
+}
+
+impl ApiError {
+    /// Parses an error body. Bodies that don't follow OpenAI's error format (e.g. from a proxy) are
+    /// kept verbatim as the message.
+    pub(crate) fn from_body(body: &str) -> Self {
+        match serde_json::from_str::<ApiErrorEnvelope>(body) {
+            Ok(envelope) => envelope.error,
+            Err(_) => ApiError {
+                message: body.to_string(),
+                kind: None,
+                param: None,
+                code: None,
+            },
+        }
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(kind) = &self.kind {
+            write!(f, " (type: {})", kind)?;
+        }
+        if let Some(code) = &self.code {
+            write!(f, " (code: {})", code)?;
+        }
+        if let Some(param) = &self.param {
+            write!(f, " (param: {})", param)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
 pub enum Error {
     /// an otherwise-unhandled error occurred making the http request
     HttpError { err: String },
-    /// a 4xx-series error occurred
-    ClientError { err: String, status: u16 },
+    /// a 4xx-series error occurred. `request_id` is the response's `x-request-id`, for support tickets
+    ClientError { err: ApiError, status: u16, request_id: Option<String> },
+    /// a 5xx-series error occurred. `request_id` is the response's `x-request-id`, for support tickets
+    ServerError { err: ApiError, status: u16, request_id: Option<String> },
     /// Error deserializing the payload
     DeserializeError { err: String },
+    /// The API reported an error part-way through a streamed response
+    StreamError { err: ApiError },
+    /// The client was misconfigured, e.g. given a header value that isn't valid in HTTP
+    ConfigError { err: String },
+    /// A [Cassette] couldn't be read or written, or had no recorded response for a request
+    CassetteError { err: String },
+    /// Output the model generated for a schema, e.g. a tool call's arguments, didn't deserialize into the
+    /// Rust type the schema describes
+    SchemaMismatch { name: String, err: String },
+    /// The model declined to produce the requested output, giving this explanation instead
+    Refusal { refusal: String },
+    /// The model hit `max_tokens` or its context length before finishing, so the output is incomplete
+    Truncated { content: String },
+    /// A [ToolRunner] was still getting tool calls after its maximum number of requests
+    MaxIterations { iterations: u32 },
 }
 
 impl Display for Error {
@@ -40,12 +155,55 @@ impl Display for Error {
             Error::HttpError { err } => {
                 write!(f, "HttpError: {}", err)
             }
-            Error::ClientError { err, status } => {
-                write!(f, "ClientError: status {} | error {}", status, err)
+            Error::ClientError { err, status, request_id } => {
+                write!(f, "ClientError: status {} | error {}", status, err)?;
+                write_request_id(f, request_id)
+            }
+            Error::ServerError { err, status, request_id } => {
+                write!(f, "ServerError: status {} | error {}", status, err)?;
+                write_request_id(f, request_id)
             }
             Error::DeserializeError { err } => {
                 write!(f, "Error deserializing payload: {}", err)
             }
+            Error::StreamError { err } => {
+                write!(f, "Error in event stream: {}", err)
+            }
+            Error::ConfigError { err } => {
+                write!(f, "Invalid client configuration: {}", err)
+            }
+            Error::CassetteError { err } => {
+                write!(f, "Cassette error: {}", err)
+            }
+            Error::SchemaMismatch { name, err } => {
+                write!(f, "Output does not match the schema for {}: {}", name, err)
+            }
+            Error::Refusal { refusal } => {
+                write!(f, "The model refused: {}", refusal)
+            }
+            Error::Truncated { content } => {
+                write!(f, "Output was cut off after {} characters", content.len())
+            }
+            Error::MaxIterations { iterations } => {
+                write!(f, "No final reply after {} requests", iterations)
+            }
+        }
+    }
+}
+
+fn write_request_id(f: &mut Formatter<'_>, request_id: &Option<String>) -> std::fmt::Result {
+    match request_id {
+        Some(request_id) => write!(f, " | request id {}", request_id),
+        None => Ok(()),
+    }
+}
+
+impl Error {
+    /// The `x-request-id` of the response that failed, for errors the API responded with
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            Error::ClientError { request_id, .. } | Error::ServerError { request_id, .. } => request_id.as_deref(),
+            _ => None,
         }
     }
 }
@@ -54,7 +212,8 @@ impl From<Method> for reqwest::Method {
     fn from(m: Method) -> Self {
         match m {
             Method::GET => reqwest::Method::GET,
-            Method::POST => reqwest::Method::POST
+            Method::POST => reqwest::Method::POST,
+            Method::DELETE => reqwest::Method::DELETE,
         }
     }
 }
@@ -64,45 +223,276 @@ pub trait Request {
     type Body: Serialize;
     const METHOD: Method;
 
-    fn endpoint(&self) -> Cow<str>;
+    fn endpoint(&self) -> Cow<'_, str>;
+
+    /// Whether, on Azure, this request is sent to the deployment (`/openai/deployments/{deployment}/...`) rather
+    /// than to the resource as a whole. True for the requests that run a model; false for e.g. models and files.
+    fn azure_deployment_scoped(&self) -> bool {
+        false
+    }
 
     fn body(&self) -> Option<&Self::Body> {
         None
     }
+
+    /// A multipart/form-data body, for requests that upload files. Takes precedence over [body](Self::body).
+    /// This is called again for each retry, since a form can only be sent once.
+    fn form(&self) -> Result<Option<reqwest::multipart::Form>, Error> {
+        Ok(None)
+    }
+
+    /// Whether the request can be sent more than once. Requests that can't, e.g. uploads streamed from a reader,
+    /// aren't retried: a failure that would have been retried is returned as it is.
+    fn replayable(&self) -> bool {
+        true
+    }
+
+    /// Turns the response body into a [Resp](Self::Resp). The body is JSON for every endpoint but the ones
+    /// returning file contents, which override this.
+    fn decode(body: &[u8]) -> Result<Self::Resp, Error> {
+        serde_json::from_slice(body).map_err(|e| Error::DeserializeError { err: e.to_string() })
+    }
+
+    /// The token usage reported in a response to this request, if this kind of response carries any.
+    /// This is what feeds [OpenAIClient::usage].
+    fn usage(_resp: &Self::Resp) -> Option<&endpoints::Usage> {
+        None
+    }
+
+    /// A rough upper bound on the tokens this request will count against a tokens-per-minute limit: the prompt
+    /// plus the most that can be generated. Used by the client's [RateLimit]; 0 for requests that don't use tokens.
+    fn estimated_tokens(&self) -> u32 {
+        0
+    }
+}
+
+/// A [Request] that can have its response delivered incrementally as server-sent events,
+/// via [OpenAIClient::send_stream]
+pub trait StreamRequest: Request {
+    /// The payload of a single event in the stream
+    type Event: DeserializeOwned;
+    /// The partial results handed back to the caller
+    type Delta;
+
+    /// Splits an event into the deltas it carries
+    fn deltas(event: Self::Event) -> Vec<Self::Delta>;
+
+    /// The token usage reported in an event, e.g. the final one sent when `stream_options.include_usage` is set.
+    /// This is what feeds [OpenAIClient::usage] for streamed responses.
+    fn event_usage(_event: &Self::Event) -> Option<&endpoints::Usage> {
+        None
+    }
 }
 
 pub struct OpenAIClient {
     api_key: ApiKey,
     client: ReqwestClient,
+    transport: Transport,
+    backend: Backend,
+    /// sent with every request, on top of auth
+    headers: HeaderMap,
+    timeout: Option<Duration>,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<RateLimiter>,
+    clock: Arc<dyn Clock>,
+    log_request_bodies: bool,
+    usage: Arc<UsageTracker>,
 }
 
 impl OpenAIClient {
+    /// A client for api.openai.com with default settings. Use [OpenAIClientBuilder] for anything else.
     pub fn new(api_key: ApiKey) -> Self {
         Self {
             api_key,
             client: ReqwestClient::new(),
+            transport: Transport::Http,
+            backend: Backend::OpenAI {
+                base_url: DEFAULT_BASE_URL.to_string(),
+            },
+            headers: HeaderMap::new(),
+            timeout: None,
+            retry_policy: RetryPolicy::default(),
+            rate_limiter: None,
+            clock: Arc::new(TokioClock),
+            log_request_bodies: false,
+            usage: Arc::new(UsageTracker::default()),
         }
     }
+
+    pub fn builder(api_key: ApiKey) -> OpenAIClientBuilder {
+        OpenAIClientBuilder::new(api_key)
+    }
+
+    /// Replaces the default [RetryPolicy]. Use [RetryPolicy::none] to disable retries entirely.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Replaces the [Clock] used to wait between retries and for rate limits
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     pub async fn send<R: Request>(&self, req: &R) -> Result<R::Resp, Error> {
+        Ok(self.send_with_meta(req).await?.body)
+    }
 
-        let mut http_req = self.client.request(R::METHOD.into(), build_url(req.endpoint()))
-            .bearer_auth(self.api_key.clone());
-        if let Some(b) = req.body() {
+    /// Like [send](Self::send), but also returns the HTTP status and what the API reported in the response
+    /// headers: the request id, processing time, serving model and remaining rate limits
+    pub async fn send_with_meta<R: Request>(&self, req: &R) -> Result<Response<R::Resp>, Error> {
+        let resp = self.execute(req).await?;
+        let meta = ResponseMeta::new(resp.status(), resp.headers());
+        let bytes = resp.bytes().await
+            .map_err(|e| Error::HttpError {err: e.to_string()})?;
+        let body = R::decode(&bytes)?;
+        if let Some(usage) = R::usage(&body) {
+            self.usage.record(usage);
+        }
+        Ok(Response { body, meta })
+    }
+
+    /// Sends a chat request and deserializes the first choice's reply into `T`, e.g. one whose
+    /// [ResponseFormat](endpoints::ResponseFormat) is a schema for `T`.
+    ///
+    /// A refusal is an [Error::Refusal] and a reply cut off by `max_tokens` is an [Error::Truncated]; only a
+    /// complete reply that still doesn't fit `T` is an [Error::SchemaMismatch].
+    pub async fn send_structured<T: DeserializeOwned>(&self, req: &endpoints::CreateChatCompletion) -> Result<T, Error> {
+        let resp = self.send(req).await?;
+        let choice = resp.choices.into_iter().next().ok_or_else(|| Error::DeserializeError {
+            err: "response has no choices".to_string(),
+        })?;
+        if let Some(refusal) = choice.message.refusal() {
+            return Err(Error::Refusal { refusal: refusal.to_string() });
+        }
+        let content = choice.message.text().unwrap_or_default().to_string();
+        if choice.finish_reason == Some(endpoints::FinishReason::Length) {
+            return Err(Error::Truncated { content });
+        }
+        serde_json::from_str(&content).map_err(|e| Error::SchemaMismatch {
+            name: match req.response_format() {
+                Some(endpoints::ResponseFormat::JsonSchema { json_schema }) => json_schema.name.clone(),
+                _ => std::any::type_name::<T>().to_string(),
+            },
+            err: e.to_string(),
+        })
+    }
+
+    /// Sends many requests, at most `concurrency` at a time, yielding each one's result in the same order as
+    /// `requests`. A failed request doesn't stop the others.
+    ///
+    /// Requests are only started as the stream is polled, so dropping the stream cancels the batch: requests in
+    /// flight are abandoned and the rest are never sent. To stop on a signal, combine it with e.g.
+    /// [`StreamExt::take_until`].
+    pub fn send_all<'a, R, I>(&'a self, requests: I, concurrency: usize) -> impl Stream<Item = Result<R::Resp, Error>> + 'a
+    where
+        R: Request + 'a,
+        I: IntoIterator<Item = R>,
+        I::IntoIter: 'a,
+    {
+        stream::iter(requests)
+            .map(move |req| async move { self.send(&req).await })
+            .buffered(concurrency.max(1))
+    }
+
+    /// Total token usage reported by every response this client has received since it was created
+    /// or last [reset](Self::reset_usage)
+    pub fn usage(&self) -> UsageTotals {
+        self.usage.snapshot()
+    }
 
+    /// Resets the usage totals to zero, returning what they were
+    pub fn reset_usage(&self) -> UsageTotals {
+        self.usage.reset()
+    }
+
+    /// Sends a request whose response is a stream of server-sent events, e.g. a
+    /// [CreateCompletion](endpoints::CreateCompletion) built with `stream(true)`.
+    ///
+    /// The returned stream ends cleanly once the API signals it is done; an error part-way through
+    /// is yielded as the stream's last item.
+    pub async fn send_stream<R: StreamRequest>(
+        &self,
+        req: &R,
+    ) -> Result<impl Stream<Item = Result<R::Delta, Error>>, Error> {
+        let resp = self.execute(req).await?;
+        Ok(sse::delta_stream::<R>(resp, self.usage.clone()))
+    }
+
+    fn build_request<R: Request>(&self, req: &R) -> Result<reqwest::RequestBuilder, Error> {
+        let http_req = self.client.request(R::METHOD.into(), self.backend.url(req)?)
+            .headers(self.headers.clone());
+        let mut http_req = self.backend.authorize(http_req, &self.api_key);
+        if let Some(timeout) = self.timeout {
+            http_req = http_req.timeout(timeout);
+        }
+        if let Some(form) = req.form()? {
+            http_req = http_req.multipart(form);
+        } else if let Some(b) = req.body() {
             http_req = http_req.json(b);
         }
-        // return Err(Error::HttpError {err: "asdf".to_string()});
-        let resp = http_req
-            .send().await
-            .map_err(|e| Error::HttpError {err: e.to_string()})?;
+        Ok(http_req)
+    }
+
+    /// Sends the request, retrying according to the client's [RetryPolicy] and waiting for its [RateLimit]
+    async fn execute<R: Request>(&self, req: &R) -> Result<reqwest::Response, Error> {
+        let span = RequestSpan::new(req, self.log_request_bodies, self.clock.now());
+        let mut attempt = 1;
+        let resp = loop {
+            let can_retry = req.replayable() && attempt < self.retry_policy.max_attempts;
+            let http_req = self.build_request(req)?.build()
+                .map_err(|e| Error::HttpError {err: e.to_string()})?;
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire(&*self.clock, req.estimated_tokens()).await;
+            }
+            let result = self.transport.execute(&self.client, http_req).await;
+            if let (Some(limiter), Ok(resp)) = (&self.rate_limiter, &result) {
+                limiter.calibrate(resp.headers());
+            }
+            let delay = match result {
+                Ok(resp) if can_retry && RetryPolicy::should_retry_status(resp.status()) => {
+                    match self.retry_policy.delay(attempt, retry::retry_after(resp.headers())) {
+                        Some(delay) => {
+                            span.retrying(attempt, &resp.status(), delay);
+                            delay
+                        }
+                        None => break resp,
+                    }
+                }
+                Ok(resp) => break resp,
+                Err(TransportError::Http(e)) if can_retry && RetryPolicy::should_retry_error(&e) => {
+                    let delay = self.retry_policy.delay(attempt, None).unwrap_or_default();
+                    span.retrying(attempt, &e, delay);
+                    delay
+                }
+                Err(e) => {
+                    let err = match e {
+                        TransportError::Http(e) => Error::HttpError {err: e.to_string()},
+                        TransportError::Other(e) => e,
+                    };
+                    span.failed(attempt, &err, self.clock.now());
+                    return Err(err);
+                }
+            };
+            self.clock.sleep(delay).await;
+            attempt += 1;
+        };
+        span.responded(attempt, &resp, self.clock.now());
         let status = resp.status();
-        if status.is_client_error() {
-            let err = resp.text().await.map_err(|e| Error::HttpError {err: e.to_string()})?;
-            return Err(Error::ClientError { status: status.as_u16(), err });
+        if status.is_client_error() || status.is_server_error() {
+            let request_id = ResponseMeta::new(status, resp.headers()).request_id;
+            let body = resp.text().await.map_err(|e| Error::HttpError {err: e.to_string()})?;
+            let err = ApiError::from_body(&body);
+            let err = if status.is_client_error() {
+                Error::ClientError { status: status.as_u16(), err, request_id }
+            } else {
+                Error::ServerError { status: status.as_u16(), err, request_id }
+            };
+            span.failed(attempt, &err, self.clock.now());
+            return Err(err);
         }
-        println!("{:?}", resp);
-        resp.json().await
-            .map_err(|e| Error::DeserializeError {err: e.to_string()})
+        Ok(resp)
     }
 }
 
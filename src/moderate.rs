@@ -0,0 +1,174 @@
+//! Moderation of text too long for a single moderations input: the text is split into overlapping chunks, the
+//! chunks are sent in batches, and the results are combined into one [ModerationVerdict].
+
+use crate::endpoints::{Categories, Moderations, ModerationsModel, ModerationsResult};
+use crate::{Error, OpenAIClient};
+use futures_util::StreamExt;
+
+/// How [moderate_text_with] splits and sends text
+#[derive(Debug, Clone)]
+pub struct ModerationOptions {
+    chunk_chars: usize,
+    overlap_chars: usize,
+    batch_size: usize,
+    concurrency: usize,
+    model: Option<ModerationsModel>,
+}
+
+impl Default for ModerationOptions {
+    fn default() -> Self {
+        Self {
+            chunk_chars: 2000,
+            overlap_chars: 200,
+            batch_size: 32,
+            concurrency: 4,
+            model: None,
+        }
+    }
+}
+
+impl ModerationOptions {
+    /// Length of each chunk, in characters. Values below 1 are treated as 1.
+    pub fn chunk_chars(mut self, chunk_chars: usize) -> Self {
+        self.chunk_chars = chunk_chars.max(1);
+        self
+    }
+
+    /// How many characters each chunk shares with the one before it, so that content straddling a chunk
+    /// boundary is still seen whole. Clamped to less than `chunk_chars`.
+    pub fn overlap_chars(mut self, overlap_chars: usize) -> Self {
+        self.overlap_chars = overlap_chars;
+        self
+    }
+
+    /// How many chunks go in each [Moderations] request
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// How many requests are in flight at once
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn model(mut self, model: ModerationsModel) -> Self {
+        self.model = Some(model);
+        self
+    }
+}
+
+/// A contiguous part of the text that was flagged. Overlapping or adjacent flagged chunks are merged into one span.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlaggedSpan {
+    /// Byte offset of the start of the span in the moderated text
+    pub start: usize,
+    /// Byte offset just past the end of the span
+    pub end: usize,
+    /// The categories any chunk in the span was flagged for
+    pub categories: Categories<bool>,
+}
+
+/// The combined result of moderating every chunk of a text
+#[derive(Debug, Clone)]
+pub struct ModerationVerdict {
+    /// Whether any chunk was flagged
+    pub flagged: bool,
+    /// The categories any chunk was flagged for
+    pub categories: Categories<bool>,
+    /// The highest score each category got in any chunk
+    pub category_scores: Categories<f64>,
+    pub flagged_spans: Vec<FlaggedSpan>,
+}
+
+/// Moderates `text` of any length with the default [ModerationOptions]
+pub async fn moderate_text(text: &str, c: &OpenAIClient) -> Result<ModerationVerdict, Error> {
+    moderate_text_with(text, &ModerationOptions::default(), c).await
+}
+
+/// Moderates `text` of any length. Fails if any of the requests does; the client has already retried
+/// transient failures by then.
+pub async fn moderate_text_with(
+    text: &str,
+    options: &ModerationOptions,
+    c: &OpenAIClient,
+) -> Result<ModerationVerdict, Error> {
+    let chunks = chunk(text, options.chunk_chars, options.overlap_chars);
+    let requests: Vec<Moderations> = chunks
+        .chunks(options.batch_size)
+        .map(|batch| Moderations {
+            input: batch.iter().map(|(start, end)| text[*start..*end].to_string()).collect(),
+            model: options.model,
+        })
+        .collect();
+    let expected: Vec<usize> = requests.iter().map(|r| r.input.len()).collect();
+
+    let mut results: Vec<ModerationsResult> = Vec::with_capacity(chunks.len());
+    let mut responses = c.send_all(requests, options.concurrency);
+    let mut batch = 0;
+    while let Some(resp) = responses.next().await {
+        let resp = resp?;
+        if resp.results.len() != expected[batch] {
+            return Err(Error::DeserializeError {
+                err: format!(
+                    "moderations returned {} results for {} inputs",
+                    resp.results.len(),
+                    expected[batch]
+                ),
+            });
+        }
+        results.extend(resp.results);
+        batch += 1;
+    }
+    Ok(aggregate(&chunks, &results))
+}
+
+/// Splits `text` into chunks of `chunk_chars` characters, each overlapping the previous one by `overlap_chars`,
+/// as byte ranges
+fn chunk(text: &str, chunk_chars: usize, overlap_chars: usize) -> Vec<(usize, usize)> {
+    let chunk_chars = chunk_chars.max(1);
+    let step = chunk_chars - overlap_chars.min(chunk_chars - 1);
+    let boundaries: Vec<usize> = text.char_indices().map(|(i, _)| i).chain(Some(text.len())).collect();
+    let chars = boundaries.len() - 1;
+    let mut chunks = vec![];
+    let mut start = 0;
+    while start < chars {
+        let end = (start + chunk_chars).min(chars);
+        chunks.push((boundaries[start], boundaries[end]));
+        if end == chars {
+            break;
+        }
+        start += step;
+    }
+    chunks
+}
+
+fn aggregate(chunks: &[(usize, usize)], results: &[ModerationsResult]) -> ModerationVerdict {
+    let mut verdict = ModerationVerdict {
+        flagged: false,
+        categories: Categories::splat(false),
+        category_scores: Categories::splat(0.0),
+        flagged_spans: vec![],
+    };
+    for (&(start, end), result) in chunks.iter().zip(results) {
+        verdict.category_scores = verdict.category_scores.zip_with(&result.category_scores, f64::max);
+        if !result.flagged {
+            continue;
+        }
+        verdict.flagged = true;
+        verdict.categories = verdict.categories.zip_with(&result.categories, |a, b| a || b);
+        match verdict.flagged_spans.last_mut() {
+            Some(span) if span.end >= start => {
+                span.end = end;
+                span.categories = span.categories.zip_with(&result.categories, |a, b| a || b);
+            }
+            _ => verdict.flagged_spans.push(FlaggedSpan {
+                start,
+                end,
+                categories: result.categories,
+            }),
+        }
+    }
+    verdict
+}
@@ -0,0 +1,156 @@
+//! Client-side rate limiting inside [OpenAIClient](crate::OpenAIClient), so that many tasks sharing one client
+//! wait for budget instead of running into 429s
+
+use crate::retry::Clock;
+use reqwest::header::HeaderMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Requests-per-minute and tokens-per-minute budgets for [OpenAIClientBuilder::rate_limit](crate::OpenAIClientBuilder::rate_limit).
+///
+/// Each budget is a token bucket that refills continuously, so a full minute's budget can be spent in a burst.
+/// The cost of a request in tokens is estimated before it is sent (see [Request::estimated_tokens](crate::Request::estimated_tokens)).
+/// A budget that isn't set is unlimited.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimit {
+    requests_per_minute: Option<u32>,
+    tokens_per_minute: Option<u32>,
+}
+
+impl RateLimit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn requests_per_minute(mut self, requests_per_minute: u32) -> Self {
+        self.requests_per_minute = Some(requests_per_minute.max(1));
+        self
+    }
+
+    pub fn tokens_per_minute(mut self, tokens_per_minute: u32) -> Self {
+        self.tokens_per_minute = Some(tokens_per_minute.max(1));
+        self
+    }
+}
+
+/// Rough token count of `text`, at the ~4 characters per token that OpenAI uses for its own estimates
+pub(crate) fn estimate_tokens(text: &str) -> u32 {
+    let chars = text.chars().count() as u32;
+    chars.div_ceil(4)
+}
+
+struct Bucket {
+    /// Budget per minute, which is also the most the bucket can hold
+    capacity: f64,
+    available: f64,
+}
+
+impl Bucket {
+    fn new(per_minute: u32) -> Self {
+        Self {
+            capacity: per_minute.into(),
+            available: per_minute.into(),
+        }
+    }
+
+    fn refill(&mut self, elapsed: Duration) {
+        self.available = (self.available + elapsed.as_secs_f64() * self.capacity / 60.0).min(self.capacity);
+    }
+
+    /// How long until `cost` is available; zero if it already is
+    fn wait_for(&self, cost: f64) -> Duration {
+        let cost = cost.min(self.capacity);
+        if self.available >= cost {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((cost - self.available) * 60.0 / self.capacity)
+        }
+    }
+
+    fn take(&mut self, cost: f64) {
+        self.available -= cost.min(self.capacity);
+    }
+
+    /// Adopts what the server reports, where it is stricter than our own view
+    fn calibrate(&mut self, limit: Option<f64>, remaining: Option<f64>) {
+        if let Some(limit) = limit.filter(|l| *l > 0.0) {
+            if limit < self.capacity {
+                self.capacity = limit;
+                self.available = self.available.min(limit);
+            }
+        }
+        if let Some(remaining) = remaining {
+            self.available = self.available.min(remaining);
+        }
+    }
+}
+
+struct State {
+    requests: Option<Bucket>,
+    tokens: Option<Bucket>,
+    refilled_at: Instant,
+}
+
+pub(crate) struct RateLimiter {
+    state: Mutex<State>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: &RateLimit, now: Instant) -> Self {
+        Self {
+            state: Mutex::new(State {
+                requests: limit.requests_per_minute.map(Bucket::new),
+                tokens: limit.tokens_per_minute.map(Bucket::new),
+                refilled_at: now,
+            }),
+        }
+    }
+
+    /// Waits until there is budget for one request costing `tokens`, then spends it. A cost bigger than the whole
+    /// per-minute budget waits for a full bucket rather than forever.
+    pub(crate) async fn acquire(&self, clock: &dyn Clock, tokens: u32) {
+        let tokens = f64::from(tokens);
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = clock.now();
+                let elapsed = now.saturating_duration_since(state.refilled_at);
+                state.refilled_at = now;
+                let State { requests, tokens: token_bucket, .. } = &mut *state;
+                for bucket in requests.iter_mut().chain(token_bucket.iter_mut()) {
+                    bucket.refill(elapsed);
+                }
+                let wait = requests
+                    .iter()
+                    .map(|b| b.wait_for(1.0))
+                    .chain(token_bucket.iter().map(|b| b.wait_for(tokens)))
+                    .max()
+                    .unwrap_or(Duration::ZERO);
+                if wait.is_zero() {
+                    requests.iter_mut().for_each(|b| b.take(1.0));
+                    token_bucket.iter_mut().for_each(|b| b.take(tokens));
+                    return;
+                }
+                wait
+            };
+            clock.sleep(wait).await;
+        }
+    }
+
+    /// Tightens the budgets from the `x-ratelimit-*` headers of a response
+    pub(crate) fn calibrate(&self, headers: &HeaderMap) {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<f64>().ok())
+        };
+        let mut state = self.state.lock().unwrap();
+        if let Some(bucket) = &mut state.requests {
+            bucket.calibrate(header("x-ratelimit-limit-requests"), header("x-ratelimit-remaining-requests"));
+        }
+        if let Some(bucket) = &mut state.tokens {
+            bucket.calibrate(header("x-ratelimit-limit-tokens"), header("x-ratelimit-remaining-tokens"));
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Response bodies together with the metadata the API sends in headers, see [OpenAIClient::send_with_meta](crate::OpenAIClient::send_with_meta)
+
+use reqwest::header::HeaderMap;
+use std::ops::Deref;
+use std::time::Duration;
+
+/// A deserialized response body and the [ResponseMeta] it came with. Derefs to the body.
+#[derive(Debug, Clone)]
+pub struct Response<T> {
+    pub body: T,
+    pub meta: ResponseMeta,
+}
+
+impl<T> Response<T> {
+    pub fn into_body(self) -> T {
+        self.body
+    }
+}
+
+impl<T> Deref for Response<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.body
+    }
+}
+
+/// What the API reports about a request in its response headers. Fields are `None` when the header was absent,
+/// e.g. for endpoints that aren't rate limited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseMeta {
+    /// The HTTP status of the response
+    pub status: u16,
+    /// `x-request-id`, the id OpenAI support asks for
+    pub request_id: Option<String>,
+    /// `openai-processing-ms`, how long the API spent on the request
+    pub processing_time: Option<Duration>,
+    /// `openai-model`, the model that actually served the request
+    pub model: Option<String>,
+    /// `x-ratelimit-remaining-requests`
+    pub remaining_requests: Option<u64>,
+    /// `x-ratelimit-remaining-tokens`
+    pub remaining_tokens: Option<u64>,
+}
+
+impl ResponseMeta {
+    pub(crate) fn new(status: reqwest::StatusCode, headers: &HeaderMap) -> Self {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+        Self {
+            status: status.as_u16(),
+            request_id: header("x-request-id").map(String::from),
+            processing_time: header("openai-processing-ms")
+                .and_then(|v| v.parse::<f64>().ok())
+                .and_then(|ms| Duration::try_from_secs_f64(ms / 1000.0).ok()),
+            model: header("openai-model").map(String::from),
+            remaining_requests: header("x-ratelimit-remaining-requests").and_then(|v| v.parse().ok()),
+            remaining_tokens: header("x-ratelimit-remaining-tokens").and_then(|v| v.parse().ok()),
+        }
+    }
+}
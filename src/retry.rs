@@ -0,0 +1,132 @@
+//! Retrying of failed requests inside [OpenAIClient](crate::OpenAIClient)
+
+use futures_util::future::BoxFuture;
+use reqwest::header::HeaderMap;
+use std::time::{Duration, Instant};
+
+/// Source of time for the client. Swap this out (via [OpenAIClientBuilder::clock](crate::OpenAIClientBuilder::clock))
+/// to test retry behaviour without actually waiting.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// The default [Clock], backed by tokio's timer
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Controls how [OpenAIClient](crate::OpenAIClient) retries requests that fail with a connection error,
+/// a 429 (rate limited) or a 5xx status.
+///
+/// Delays grow exponentially from `base_delay`, capped at `max_delay`, unless the response carries a
+/// `Retry-After` header, in which case that is used instead. A `Retry-After` longer than `max_retry_after`
+/// isn't waited out: the request fails with the response that asked for it.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub(crate) max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    max_retry_after: Duration,
+    jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+            max_retry_after: Duration::from_secs(60),
+            jitter: 0.25,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Total number of attempts, including the first one. Values below 1 are treated as 1.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry; each subsequent retry doubles it
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Upper bound on the computed backoff. Does not apply to delays requested via `Retry-After`.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// The longest `Retry-After` the client will wait for before retrying. Defaults to 60 seconds.
+    pub fn max_retry_after(mut self, max_retry_after: Duration) -> Self {
+        self.max_retry_after = max_retry_after;
+        self
+    }
+
+    /// Fraction of each delay, in [0, 1], that is randomised away so that many clients don't retry in lockstep.
+    /// 0 disables jitter.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub(crate) fn should_retry_status(status: reqwest::StatusCode) -> bool {
+        status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+    }
+
+    pub(crate) fn should_retry_error(err: &reqwest::Error) -> bool {
+        err.is_connect() || err.is_timeout() || err.is_request()
+    }
+
+    /// How long to wait after the given (1-based) attempt failed, or `None` to give up because the server
+    /// asked for a longer wait than `max_retry_after`
+    pub(crate) fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if let Some(retry_after) = retry_after {
+            return Some(retry_after).filter(|d| *d <= self.max_retry_after);
+        }
+        let exp = attempt.saturating_sub(1).min(31);
+        let backoff = self
+            .base_delay
+            .checked_mul(1 << exp)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        if self.jitter > 0.0 {
+            Some(backoff.mul_f64(1.0 - self.jitter * fastrand::f64()))
+        } else {
+            Some(backoff)
+        }
+    }
+}
+
+/// Reads the delay the server asked for, preferring OpenAI's millisecond-precision `retry-after-ms`
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.trim().parse::<f64>().ok()) {
+        return Duration::try_from_secs_f64(ms / 1000.0).ok();
+    }
+    header("retry-after")
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+}
@@ -0,0 +1,202 @@
+//! Decoding of `text/event-stream` response bodies, as returned by the endpoints when `stream` is set.
+//! Only the parts of https://html.spec.whatwg.org/multipage/server-sent-events.html that OpenAI actually
+//! uses are handled: `data:` fields and blank-line event boundaries. Everything else is ignored.
+
+use crate::usage_tracker::UsageTracker;
+use crate::{ApiErrorEnvelope, Error, StreamRequest};
+use futures_util::stream::{self, Stream, StreamExt};
+use std::sync::Arc;
+
+/// The payload OpenAI sends to signal the end of a stream
+const DONE: &str = "[DONE]";
+
+/// Incrementally splits a byte stream into event payloads.
+/// Bytes can arrive split at arbitrary points, so anything after the last newline is held until more arrives.
+#[derive(Default)]
+pub(crate) struct EventDecoder {
+    buf: Vec<u8>,
+    data: Option<String>,
+}
+
+impl EventDecoder {
+    /// Feeds in the next piece of the body, returning the `data` of every event it completed
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buf.extend_from_slice(bytes);
+        let mut events = vec![];
+        while let Some(pos) = self.buf.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                if let Some(data) = self.data.take() {
+                    events.push(data);
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                let value = value.strip_prefix(' ').unwrap_or(value);
+                match self.data {
+                    Some(ref mut data) => {
+                        data.push('\n');
+                        data.push_str(value);
+                    }
+                    None => self.data = Some(value.to_string()),
+                }
+            }
+        }
+        events
+    }
+}
+
+fn parse_event<R: StreamRequest>(data: &str, usage: &UsageTracker) -> Result<Vec<R::Delta>, Error> {
+    match serde_json::from_str::<R::Event>(data) {
+        Ok(event) => {
+            if let Some(u) = R::event_usage(&event) {
+                usage.record(u);
+            }
+            Ok(R::deltas(event))
+        }
+        Err(e) => match serde_json::from_str::<ApiErrorEnvelope>(data) {
+            Ok(envelope) => Err(Error::StreamError { err: envelope.error }),
+            Err(_) => Err(Error::DeserializeError { err: e.to_string() }),
+        },
+    }
+}
+
+/// Turns a streaming response into the deltas of each event it carries, recording any usage the events report.
+/// The stream ends after `[DONE]` or after the first error.
+pub(crate) fn delta_stream<R: StreamRequest>(
+    resp: reqwest::Response,
+    usage: Arc<UsageTracker>,
+) -> impl Stream<Item = Result<R::Delta, Error>> {
+    let body = resp.bytes_stream().boxed();
+    stream::unfold(
+        (body, EventDecoder::default(), usage, false),
+        |(mut body, mut decoder, usage, finished)| async move {
+            if finished {
+                return None;
+            }
+            loop {
+                let events = match body.next().await {
+                    Some(Ok(bytes)) => decoder.push(&bytes),
+                    Some(Err(e)) => {
+                        let err = Error::HttpError { err: e.to_string() };
+                        return Some((vec![Err(err)], (body, decoder, usage, true)));
+                    }
+                    None => {
+                        let err = Error::HttpError {
+                            err: "event stream ended without [DONE]".to_string(),
+                        };
+                        return Some((vec![Err(err)], (body, decoder, usage, true)));
+                    }
+                };
+                if events.is_empty() {
+                    continue;
+                }
+
+                let mut deltas = vec![];
+                let mut finished = false;
+                for data in events {
+                    if data == DONE {
+                        finished = true;
+                        break;
+                    }
+                    match parse_event::<R>(&data, &usage) {
+                        Ok(ds) => deltas.extend(ds.into_iter().map(Ok)),
+                        Err(e) => {
+                            deltas.push(Err(e));
+                            finished = true;
+                            break;
+                        }
+                    }
+                }
+                return Some((deltas, (body, decoder, usage, finished)));
+            }
+        },
+    )
+    .flat_map(stream::iter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::CreateCompletion;
+
+    fn event(text: &str) -> String {
+        serde_json::json!({
+            "id": "cmpl-1", "object": "text_completion", "created": 0, "model": "m",
+            "choices": [{"text": text, "index": 0, "logprobs": null, "finish_reason": null}]
+        })
+        .to_string()
+    }
+
+    /// Runs `chunks`, delivered one at a time, through [delta_stream], returning each delta's text or error
+    async fn stream_texts(chunks: Vec<String>) -> Vec<Result<String, Error>> {
+        let chunks = chunks.into_iter().map(Ok::<_, std::io::Error>);
+        let body = reqwest::Body::wrap_stream(stream::iter(chunks));
+        let resp = reqwest::Response::from(http::Response::new(body));
+        delta_stream::<CreateCompletion>(resp, Arc::default())
+            .map(|delta| delta.map(|choice| choice.text))
+            .collect()
+            .await
+    }
+
+    #[test]
+    fn joins_events_split_across_chunks() {
+        let mut decoder = EventDecoder::default();
+        assert!(decoder.push(b"da").is_empty());
+        assert!(decoder.push(b"ta: {\"a\":").is_empty());
+        assert!(decoder.push(b" 1}\n").is_empty());
+        assert_eq!(decoder.push(b"\ndata: 2\n\n"), vec!["{\"a\": 1}", "2"]);
+    }
+
+    #[test]
+    fn handles_crlf_line_endings() {
+        let mut decoder = EventDecoder::default();
+        assert_eq!(decoder.push(b"data: one\r\n\r\ndata: two\r"), vec!["one"]);
+        assert_eq!(decoder.push(b"\n\r\n"), vec!["two"]);
+    }
+
+    #[test]
+    fn joins_multi_line_data_and_ignores_other_fields() {
+        let mut decoder = EventDecoder::default();
+        let events = decoder.push(b": comment\nevent: message\ndata: {\ndata:\"a\": 1}\nid: 7\n\n");
+        assert_eq!(events, vec!["{\n\"a\": 1}"]);
+    }
+
+    #[tokio::test]
+    async fn stops_at_done() {
+        let texts = stream_texts(vec![
+            format!("data: {}\n\ndata: {}\n", event("a"), event("b")),
+            format!("\ndata: [DONE]\n\ndata: {}\n\n", event("ignored")),
+        ])
+        .await;
+        let texts: Vec<String> = texts.into_iter().map(Result::unwrap).collect();
+        assert_eq!(texts, vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn ends_with_stream_error_on_error_envelope() {
+        let error = r#"{"error": {"message": "overloaded", "type": "server_error", "param": null, "code": null}}"#;
+        let texts = stream_texts(vec![
+            format!("data: {}\n\n", event("a")),
+            format!("data: {}\n\ndata: {}\n\n", error, event("b")),
+        ])
+        .await;
+        assert_eq!(texts.len(), 2);
+        assert_eq!(texts[0].as_ref().unwrap(), "a");
+        match &texts[1] {
+            Err(Error::StreamError { err }) => assert_eq!(err.message, "overloaded"),
+            other => panic!("expected a StreamError, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_eof_without_done() {
+        let texts = stream_texts(vec![format!("data: {}\n\n", event("a"))]).await;
+        assert_eq!(texts.len(), 2);
+        assert_eq!(texts[0].as_ref().unwrap(), "a");
+        match &texts[1] {
+            Err(Error::HttpError { err }) => assert!(err.contains("without [DONE]"), "{}", err),
+            other => panic!("expected an HttpError, got {:?}", other),
+        }
+    }
+}
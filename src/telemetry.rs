@@ -0,0 +1,85 @@
+//! Instrumentation of requests made by [OpenAIClient](crate::OpenAIClient).
+//!
+//! With the `tracing` feature enabled, each request gets a `openai_request` span recording the method, endpoint,
+//! status, `x-request-id`, latency and number of attempts, with events for retries and failures. Request bodies
+//! contain prompts and other user content, so they are only emitted if explicitly enabled via
+//! [OpenAIClientBuilder::log_request_bodies](crate::OpenAIClientBuilder::log_request_bodies).
+//!
+//! Without the feature, everything here compiles down to nothing.
+
+use crate::{Error, Request};
+use std::time::{Duration, Instant};
+
+pub(crate) struct RequestSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "tracing")]
+    start: Instant,
+}
+
+#[cfg(feature = "tracing")]
+impl RequestSpan {
+    pub(crate) fn new<R: Request>(req: &R, log_body: bool, start: Instant) -> Self {
+        let method = reqwest::Method::from(R::METHOD);
+        let span = tracing::debug_span!(
+            "openai_request",
+            method = %method,
+            endpoint = %req.endpoint(),
+            status = tracing::field::Empty,
+            request_id = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+            attempts = tracing::field::Empty,
+        );
+        if let Some(body) = req.body() {
+            if log_body {
+                let body = serde_json::to_string(body).unwrap_or_else(|e| format!("<unserializable: {}>", e));
+                tracing::trace!(parent: &span, body = %body, "request body");
+            } else {
+                tracing::trace!(parent: &span, body = "<redacted>", "request body");
+            }
+        }
+        Self { span, start }
+    }
+
+    pub(crate) fn retrying(&self, attempt: u32, reason: &dyn std::fmt::Display, delay: Duration) {
+        tracing::debug!(
+            parent: &self.span,
+            attempt,
+            reason = %reason,
+            delay_ms = delay.as_millis() as u64,
+            "retrying request"
+        );
+    }
+
+    pub(crate) fn responded(&self, attempts: u32, resp: &reqwest::Response, now: Instant) {
+        let request_id = resp
+            .headers()
+            .get("x-request-id")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+        self.span.record("status", resp.status().as_u16());
+        self.span.record("request_id", request_id);
+        self.span.record("latency_ms", now.saturating_duration_since(self.start).as_millis() as u64);
+        self.span.record("attempts", attempts);
+        tracing::debug!(parent: &self.span, "response received");
+    }
+
+    pub(crate) fn failed(&self, attempts: u32, err: &Error, now: Instant) {
+        self.span.record("latency_ms", now.saturating_duration_since(self.start).as_millis() as u64);
+        self.span.record("attempts", attempts);
+        tracing::warn!(parent: &self.span, error = %err, "request failed");
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl RequestSpan {
+    pub(crate) fn new<R: Request>(_req: &R, _log_body: bool, _start: Instant) -> Self {
+        Self {}
+    }
+
+    pub(crate) fn retrying(&self, _attempt: u32, _reason: &dyn std::fmt::Display, _delay: Duration) {}
+
+    pub(crate) fn responded(&self, _attempts: u32, _resp: &reqwest::Response, _now: Instant) {}
+
+    pub(crate) fn failed(&self, _attempts: u32, _err: &Error, _now: Instant) {}
+}
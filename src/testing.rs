@@ -0,0 +1,415 @@
+//! An in-process mock of the OpenAI API, for exercising code built on [OpenAIClient] without network access
+//! or an api key. Enabled by the `testing` feature.
+//!
+//! ```no_run
+//! # async fn example() {
+//! use openai_rust_client::testing::{MockResponse, MockServer};
+//! use openai_rust_client::endpoints::ListModels;
+//!
+//! let server = MockServer::start().await;
+//! server.enqueue("models", MockResponse::rate_limited(0));
+//! let client = server.client();
+//! // the first attempt is rate limited; the client retries and gets the canned model list
+//! let models = client.send(&ListModels {}).await.unwrap();
+//! assert_eq!(server.received().len(), 2);
+//! # }
+//! ```
+
+use crate::{ApiKey, OpenAIClient, OpenAIClientBuilder};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Server};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// The api key [MockServer::client] authenticates with
+pub const MOCK_API_KEY: &str = "sk-mock";
+
+/// A response for the [MockServer] to send
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    delay: Option<Duration>,
+}
+
+impl MockResponse {
+    /// A response with the given status and JSON body
+    pub fn json<T: Serialize>(status: u16, body: &T) -> Self {
+        Self {
+            status,
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body: serde_json::to_vec(body).expect("mock response body should serialize"),
+            delay: None,
+        }
+    }
+
+    /// A 200 response with the given JSON body
+    pub fn ok<T: Serialize>(body: &T) -> Self {
+        Self::json(200, body)
+    }
+
+    /// A response in OpenAI's error format
+    pub fn error(status: u16, kind: &str, message: &str) -> Self {
+        Self::json(
+            status,
+            &json!({"error": {"message": message, "type": kind, "param": null, "code": null}}),
+        )
+    }
+
+    /// A 429 asking the client to retry after the given number of seconds
+    pub fn rate_limited(retry_after_secs: u64) -> Self {
+        Self::error(429, "requests", "Rate limit reached").header("retry-after", retry_after_secs.to_string())
+    }
+
+    /// A 500 in OpenAI's error format
+    pub fn server_error() -> Self {
+        Self::error(500, "server_error", "The server had an error while processing your request")
+    }
+
+    /// A `text/event-stream` response sending each value as an event, followed by `[DONE]`
+    pub fn event_stream<T: Serialize>(events: &[T]) -> Self {
+        let mut body = String::new();
+        for event in events {
+            let data = serde_json::to_string(event).expect("mock event should serialize");
+            body.push_str(&format!("data: {}\n\n", data));
+        }
+        body.push_str("data: [DONE]\n\n");
+        Self {
+            status: 200,
+            headers: vec![("content-type".to_string(), "text/event-stream".to_string())],
+            body: body.into_bytes(),
+            delay: None,
+        }
+    }
+
+    /// A response with an arbitrary body
+    pub fn raw<B: Into<Vec<u8>>>(status: u16, content_type: &str, body: B) -> Self {
+        Self {
+            status,
+            headers: vec![("content-type".to_string(), content_type.to_string())],
+            body: body.into(),
+            delay: None,
+        }
+    }
+
+    /// Adds a header to the response
+    pub fn header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Waits before responding; combine with [OpenAIClientBuilder::timeout] to simulate timeouts
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+}
+
+/// A request the [MockServer] received
+#[derive(Debug, Clone)]
+pub struct ReceivedRequest {
+    pub method: String,
+    /// The endpoint, relative to the base url, e.g. `chat/completions`
+    pub endpoint: String,
+    /// The query string, if there was one
+    pub query: Option<String>,
+    /// Header names are lowercase
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl ReceivedRequest {
+    /// The body parsed as JSON, or `Value::Null` if it wasn't JSON
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap_or(Value::Null)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(|s| s.as_str())
+    }
+}
+
+type Handler = Box<dyn Fn(&ReceivedRequest) -> MockResponse + Send + Sync>;
+
+#[derive(Default)]
+struct State {
+    received: Vec<ReceivedRequest>,
+    queued: HashMap<String, VecDeque<MockResponse>>,
+    handlers: HashMap<String, Handler>,
+}
+
+impl State {
+    fn respond(&mut self, req: &ReceivedRequest) -> MockResponse {
+        if let Some(resp) = self.queued.get_mut(&req.endpoint).and_then(|q| q.pop_front()) {
+            return resp;
+        }
+        if let Some(handler) = self.handlers.get(&req.endpoint) {
+            return handler(req);
+        }
+        canned_response(req)
+    }
+}
+
+/// A mock OpenAI API listening on localhost.
+///
+/// Each request is answered by, in order of preference: the next response [enqueued](Self::enqueue) for its
+/// endpoint, the [handler](Self::handle) registered for its endpoint, or a canned response for the endpoints the
+/// crate knows about. Anything else gets a 404.
+///
+/// The server shuts down when this is dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockServer {
+    /// Starts a server on an arbitrary free port. Must be called from within a tokio runtime.
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(State::default()));
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_conn| {
+            let state = service_state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req))) }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let addr = server.local_addr();
+        let (shutdown, on_shutdown) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            let _ = on_shutdown.await;
+        }));
+        Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    /// The base url to point a client at
+    pub fn base_url(&self) -> String {
+        format!("http://{}/v1", self.addr)
+    }
+
+    /// A client builder already pointed at this server
+    pub fn client_builder(&self) -> OpenAIClientBuilder {
+        OpenAIClient::builder(ApiKey::new(MOCK_API_KEY.to_string())).base_url(self.base_url())
+    }
+
+    /// A client pointed at this server, retrying without waiting
+    pub fn client(&self) -> OpenAIClient {
+        self.client_builder()
+            .retry_policy(crate::RetryPolicy::default().base_delay(Duration::ZERO).jitter(0.0))
+            .build()
+            .expect("mock client config is valid")
+    }
+
+    /// Queues a one-off response for the next request to `endpoint` (e.g. `chat/completions`).
+    /// Responses queued for the same endpoint are sent in order.
+    pub fn enqueue<S: Into<String>>(&self, endpoint: S, response: MockResponse) {
+        self.state
+            .lock()
+            .unwrap()
+            .queued
+            .entry(endpoint.into())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// Answers every request to `endpoint` that doesn't have a queued response using `handler`
+    pub fn handle<S, F>(&self, endpoint: S, handler: F)
+    where
+        S: Into<String>,
+        F: Fn(&ReceivedRequest) -> MockResponse + Send + Sync + 'static,
+    {
+        self.state
+            .lock()
+            .unwrap()
+            .handlers
+            .insert(endpoint.into(), Box::new(handler));
+    }
+
+    /// Every request received so far, oldest first
+    pub fn received(&self) -> Vec<ReceivedRequest> {
+        self.state.lock().unwrap().received.clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn handle(state: Arc<Mutex<State>>, req: hyper::Request<Body>) -> Result<hyper::Response<Body>, Infallible> {
+    let method = req.method().to_string();
+    let endpoint = req.uri().path().trim_start_matches('/');
+    let endpoint = endpoint.strip_prefix("v1/").unwrap_or(endpoint).to_string();
+    let query = req.uri().query().map(|q| q.to_string());
+    let headers = req
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.as_str().to_string(), String::from_utf8_lossy(v.as_bytes()).into_owned()))
+        .collect();
+    let body = hyper::body::to_bytes(req.into_body()).await.map(|b| b.to_vec()).unwrap_or_default();
+    let received = ReceivedRequest {
+        method,
+        endpoint,
+        query,
+        headers,
+        body,
+    };
+
+    let mock = {
+        let mut state = state.lock().unwrap();
+        let mock = state.respond(&received);
+        state.received.push(received);
+        mock
+    };
+    if let Some(delay) = mock.delay {
+        tokio::time::sleep(delay).await;
+    }
+    let mut resp = hyper::Response::builder().status(mock.status);
+    if !mock.headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("x-request-id")) {
+        resp = resp.header("x-request-id", "req_mock");
+    }
+    for (name, value) in &mock.headers {
+        resp = resp.header(name.as_str(), value.as_str());
+    }
+    Ok(resp
+        .body(Body::from(mock.body))
+        .expect("mock response should be valid"))
+}
+
+/// A plausible response for each endpoint the crate supports
+fn canned_response(req: &ReceivedRequest) -> MockResponse {
+    let body = req.json();
+    let model = body["model"].as_str().unwrap_or("mock-model").to_string();
+    let streaming = body["stream"].as_bool().unwrap_or(false);
+    let endpoint = req.endpoint.as_str();
+    let usage = json!({"prompt_tokens": 5, "completion_tokens": 4, "total_tokens": 9});
+
+    if endpoint == "chat/completions" {
+        if streaming {
+            let chunk = |delta: Value, finish_reason: Value| {
+                json!({
+                    "id": "chatcmpl-mock", "object": "chat.completion.chunk", "created": 0, "model": model,
+                    "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}]
+                })
+            };
+            let mut events = vec![
+                chunk(json!({"role": "assistant", "content": "This is"}), Value::Null),
+                chunk(json!({"content": " a test."}), json!("stop")),
+            ];
+            if body["stream_options"]["include_usage"] == true {
+                events.push(json!({
+                    "id": "chatcmpl-mock", "object": "chat.completion.chunk", "created": 0, "model": model,
+                    "choices": [], "usage": usage
+                }));
+            }
+            return MockResponse::event_stream(&events);
+        }
+        return MockResponse::ok(&json!({
+            "id": "chatcmpl-mock", "object": "chat.completion", "created": 0, "model": model,
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "This is a test."}, "finish_reason": "stop"}],
+            "usage": usage,
+        }));
+    }
+    if endpoint == "completions" {
+        let choice = |text: &str, finish_reason: Value| {
+            json!({"text": text, "index": 0, "logprobs": null, "finish_reason": finish_reason})
+        };
+        if streaming {
+            let chunk = |choice: Value| {
+                json!({"id": "cmpl-mock", "object": "text_completion", "created": 0, "model": model, "choices": [choice]})
+            };
+            let mut events = vec![chunk(choice("This is", Value::Null)), chunk(choice(" a test.", json!("stop")))];
+            if body["stream_options"]["include_usage"] == true {
+                events.push(json!({
+                    "id": "cmpl-mock", "object": "text_completion", "created": 0, "model": model,
+                    "choices": [], "usage": usage
+                }));
+            }
+            return MockResponse::event_stream(&events);
+        }
+        return MockResponse::ok(&json!({
+            "id": "cmpl-mock", "object": "text_completion", "created": 0, "model": model,
+            "choices": [choice("This is a test.", json!("stop"))],
+            "usage": usage,
+        }));
+    }
+    if endpoint == "moderations" {
+        let categories = |v: Value| {
+            json!({
+                "hate": v, "hate/threatening": v, "self-harm": v, "sexual": v,
+                "sexual/minors": v, "violence": v, "violence/graphic": v
+            })
+        };
+        let inputs = body["input"].as_array().map(|a| a.len()).unwrap_or(1);
+        let result = json!({"categories": categories(json!(false)), "category_scores": categories(json!(0.0)), "flagged": false});
+        return MockResponse::ok(&json!({
+            "id": "modr-mock", "model": "text-moderation-mock", "results": vec![result; inputs]
+        }));
+    }
+    if endpoint == "embeddings" {
+        let inputs = body["input"].as_array().map(|a| a.len()).unwrap_or(1);
+        let data: Vec<Value> = (0..inputs)
+            .map(|i| json!({"object": "embedding", "index": i, "embedding": [1.0, 0.0, 0.0]}))
+            .collect();
+        return MockResponse::ok(&json!({
+            "object": "list", "model": model, "data": data, "usage": {"prompt_tokens": 5, "total_tokens": 5}
+        }));
+    }
+    if endpoint == "engines" {
+        return MockResponse::ok(&json!({
+            "object": "list",
+            "data": [{"id": "mock-engine", "object": "engine", "owner": "openai", "ready": true}]
+        }));
+    }
+    let mock_model = |id: &str| json!({"id": id, "object": "model", "created": 0, "owned_by": "openai"});
+    if endpoint == "models" {
+        return MockResponse::ok(&json!({"object": "list", "data": [mock_model("mock-model")]}));
+    }
+    if let Some(id) = endpoint.strip_prefix("models/") {
+        if req.method == "DELETE" {
+            return MockResponse::ok(&json!({"id": id, "object": "model", "deleted": true}));
+        }
+        return MockResponse::ok(&mock_model(id));
+    }
+    let mock_file = |id: &str, filename: &str, bytes: usize| {
+        json!({
+            "id": id, "object": "file", "bytes": bytes, "created_at": 0, "filename": filename,
+            "purpose": "fine-tune"
+        })
+    };
+    if endpoint == "files" {
+        if req.method == "POST" {
+            let body = String::from_utf8_lossy(&req.body);
+            let filename = body
+                .split("filename=\"")
+                .nth(1)
+                .and_then(|rest| rest.split('"').next())
+                .unwrap_or("mock.jsonl");
+            return MockResponse::ok(&mock_file("file-mock", filename, req.body.len()));
+        }
+        return MockResponse::ok(&json!({"object": "list", "data": [mock_file("file-mock", "mock.jsonl", 5)]}));
+    }
+    if let Some(id) = endpoint.strip_prefix("files/") {
+        if let Some(id) = id.strip_suffix("/content") {
+            return MockResponse::raw(200, "application/octet-stream", format!("contents of {}", id));
+        }
+        if req.method == "DELETE" {
+            return MockResponse::ok(&json!({"id": id, "object": "file", "deleted": true}));
+        }
+        return MockResponse::ok(&mock_file(id, "mock.jsonl", 5));
+    }
+    MockResponse::error(404, "invalid_request_error", &format!("Unknown endpoint: {}", endpoint))
+}
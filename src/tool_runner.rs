@@ -0,0 +1,228 @@
+//! An agent loop: send a chat request, run whichever tools the model calls, send their results back, and repeat
+//! until the model replies without calling any.
+
+use crate::endpoints::{ChatMessage, CreateChatCompletion, FinishReason, Tool, ToolCall, ToolChoice};
+use crate::{Error, OpenAIClient};
+use futures_util::future::{self, BoxFuture, Either, FutureExt};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::time::Duration;
+
+type Handler = Box<dyn Fn(ToolCall) -> BoxFuture<'static, Result<String, String>> + Send + Sync>;
+
+struct RegisteredTool {
+    tool: Tool,
+    handler: Handler,
+    timeout: Option<Duration>,
+}
+
+/// Drives a chat conversation through tool calls, using handlers registered by function name.
+///
+/// The tool calls in each reply run concurrently. A handler that fails, times out or doesn't exist doesn't end
+/// the run: the model is told what went wrong, as the tool's result, and can decide what to do about it.
+pub struct ToolRunner<'a> {
+    client: &'a OpenAIClient,
+    tools: HashMap<String, RegisteredTool>,
+    max_iterations: u32,
+    timeout: Option<Duration>,
+}
+
+impl<'a> ToolRunner<'a> {
+    pub fn new(client: &'a OpenAIClient) -> Self {
+        Self {
+            client,
+            tools: HashMap::new(),
+            max_iterations: 10,
+            timeout: None,
+        }
+    }
+
+    /// The most chat requests a run makes before giving up with [Error::MaxIterations]. Defaults to 10.
+    pub fn max_iterations(mut self, max_iterations: u32) -> Self {
+        self.max_iterations = max_iterations.max(1);
+        self
+    }
+
+    /// How long any handler may take, unless registered with its own timeout. No limit by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Registers `handler` to answer calls to `tool`. The handler's `Ok` is sent to the model as the result;
+    /// an `Err` is sent as an error message.
+    pub fn register<F, Fut>(self, tool: Tool, handler: F) -> Self
+    where
+        F: Fn(ToolCall) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        self.register_with_timeout(tool, None, handler)
+    }
+
+    /// Like [register](Self::register), with a timeout for just this tool
+    pub fn register_with_timeout<F, Fut>(mut self, tool: Tool, timeout: Option<Duration>, handler: F) -> Self
+    where
+        F: Fn(ToolCall) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        let handler: Handler = Box::new(move |call| handler(call).boxed());
+        self.tools
+            .insert(tool.name().to_string(), RegisteredTool { tool, handler, timeout });
+        self
+    }
+
+    /// Registers a handler that takes the tool's arguments already deserialized. Arguments that don't fit `T`
+    /// are reported back to the model without calling the handler.
+    #[cfg(feature = "schemars")]
+    pub fn register_typed<T, F, Fut>(self, tool: crate::endpoints::TypedTool<T>, handler: F) -> Self
+    where
+        T: schemars::JsonSchema + serde::de::DeserializeOwned + Send + 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        let handler = std::sync::Arc::new(handler);
+        self.register(tool.tool(), move |call: ToolCall| {
+            let handler = handler.clone();
+            async move {
+                match call.parse_arguments::<T>() {
+                    Ok(args) => handler(args).await,
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+        })
+    }
+
+    /// Runs the conversation in `req` to a final reply. Registered tools missing from the request are added
+    /// to it.
+    ///
+    /// A `tool_choice` forcing a tool call only applies to the first request; once tool results have been sent
+    /// back it becomes [ToolChoice::Auto], so the model can finish with a reply.
+    pub async fn run(&self, mut req: CreateChatCompletion) -> ToolRun {
+        for registered in self.tools.values() {
+            if !req.tools_mut().iter().any(|t| t.name() == registered.tool.name()) {
+                req.tools_mut().push(registered.tool.clone());
+            }
+        }
+        let mut transcript = vec![];
+        let reply = self.drive(&mut req, &mut transcript).await;
+        ToolRun {
+            reply,
+            messages: req.messages().to_vec(),
+            transcript,
+        }
+    }
+
+    async fn drive(&self, req: &mut CreateChatCompletion, transcript: &mut Vec<Step>) -> Result<ChatMessage, Error> {
+        for iteration in 1..=self.max_iterations {
+            let resp = self.client.send(&*req).await?;
+            let choice = resp.choices.into_iter().next().ok_or_else(|| Error::DeserializeError {
+                err: "response has no choices".to_string(),
+            })?;
+            let message = choice.message;
+            transcript.push(Step::Reply {
+                iteration,
+                message: message.clone(),
+                finish_reason: choice.finish_reason,
+            });
+            req.push_message(message.clone());
+            if message.tool_calls().is_empty() {
+                return Ok(message);
+            }
+
+            let results = future::join_all(message.tool_calls().iter().map(|call| self.call(call.clone()))).await;
+            for (call, outcome, elapsed) in results {
+                req.push_message(ChatMessage::tool(&call.id, outcome.to_string()));
+                transcript.push(Step::ToolCall {
+                    iteration,
+                    call,
+                    outcome,
+                    elapsed,
+                });
+            }
+            let choice = req.tool_choice_mut();
+            if matches!(choice, Some(ToolChoice::Required) | Some(ToolChoice::Function(_))) {
+                *choice = Some(ToolChoice::Auto);
+            }
+        }
+        Err(Error::MaxIterations {
+            iterations: self.max_iterations,
+        })
+    }
+
+    async fn call(&self, call: ToolCall) -> (ToolCall, ToolOutcome, Duration) {
+        let clock = &self.client.clock;
+        let start = clock.now();
+        let outcome = match self.tools.get(&call.function.name) {
+            None => ToolOutcome::UnknownTool,
+            Some(registered) => {
+                let running = (registered.handler)(call.clone());
+                let result = match registered.timeout.or(self.timeout) {
+                    None => Some(running.await),
+                    Some(timeout) => match future::select(running, clock.sleep(timeout)).await {
+                        Either::Left((result, _)) => Some(result),
+                        Either::Right(_) => None,
+                    },
+                };
+                match result {
+                    Some(Ok(content)) => ToolOutcome::Success(content),
+                    Some(Err(err)) => ToolOutcome::Failed(err),
+                    None => ToolOutcome::TimedOut(registered.timeout.or(self.timeout).unwrap_or_default()),
+                }
+            }
+        };
+        let elapsed = clock.now().saturating_duration_since(start);
+        (call, outcome, elapsed)
+    }
+}
+
+/// What came of running one tool call. Its [Display] is what the model is sent as the result.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolOutcome {
+    Success(String),
+    /// The handler returned an error
+    Failed(String),
+    /// The handler didn't finish within its timeout, and was dropped
+    TimedOut(Duration),
+    /// No handler is registered under the name the model called
+    UnknownTool,
+}
+
+impl Display for ToolOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToolOutcome::Success(content) => write!(f, "{}", content),
+            ToolOutcome::Failed(err) => write!(f, "Error: {}", err),
+            ToolOutcome::TimedOut(timeout) => write!(f, "Error: the tool timed out after {:?}", timeout),
+            ToolOutcome::UnknownTool => write!(f, "Error: there is no such tool"),
+        }
+    }
+}
+
+/// One step of a [ToolRun], for auditing
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// The model replied to the `iteration`th request
+    Reply {
+        iteration: u32,
+        message: ChatMessage,
+        finish_reason: Option<FinishReason>,
+    },
+    /// A tool the model called in the `iteration`th reply was run
+    ToolCall {
+        iteration: u32,
+        call: ToolCall,
+        outcome: ToolOutcome,
+        elapsed: Duration,
+    },
+}
+
+/// The result of [ToolRunner::run]. The messages and transcript are kept even if the run failed.
+#[derive(Debug)]
+pub struct ToolRun {
+    /// The model's final reply, without tool calls, or what stopped the run
+    pub reply: Result<ChatMessage, Error>,
+    /// The whole conversation: the request's messages followed by every reply and tool result
+    pub messages: Vec<ChatMessage>,
+    pub transcript: Vec<Step>,
+}
@@ -0,0 +1,41 @@
+//! The layer beneath [OpenAIClient](crate::OpenAIClient) that actually gets a response for a request:
+//! either over HTTP, or via a [Cassette]
+
+use crate::cassette::Cassette;
+use crate::Error;
+
+pub(crate) enum Transport {
+    Http,
+    Cassette(Cassette),
+}
+
+pub(crate) enum TransportError {
+    /// Failed to get a response over HTTP. Some of these are worth retrying.
+    Http(reqwest::Error),
+    Other(Error),
+}
+
+impl From<reqwest::Error> for TransportError {
+    fn from(e: reqwest::Error) -> Self {
+        TransportError::Http(e)
+    }
+}
+
+impl From<Error> for TransportError {
+    fn from(e: Error) -> Self {
+        TransportError::Other(e)
+    }
+}
+
+impl Transport {
+    pub(crate) async fn execute(
+        &self,
+        client: &reqwest::Client,
+        http_req: reqwest::Request,
+    ) -> Result<reqwest::Response, TransportError> {
+        match self {
+            Transport::Http => Ok(client.execute(http_req).await?),
+            Transport::Cassette(cassette) => cassette.execute(client, http_req).await,
+        }
+    }
+}
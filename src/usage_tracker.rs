@@ -0,0 +1,40 @@
+use crate::endpoints::Usage;
+use std::sync::Mutex;
+
+/// Running totals of the token usage reported by the API, see [OpenAIClient::usage](crate::OpenAIClient::usage)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UsageTotals {
+    /// Number of responses that reported usage
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, usage: &Usage) {
+        self.requests += 1;
+        self.prompt_tokens += u64::from(usage.prompt_tokens);
+        self.completion_tokens += u64::from(usage.completion_tokens);
+        self.total_tokens += u64::from(usage.total_tokens);
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct UsageTracker {
+    totals: Mutex<UsageTotals>,
+}
+
+impl UsageTracker {
+    pub(crate) fn record(&self, usage: &Usage) {
+        self.totals.lock().unwrap().add(usage);
+    }
+
+    pub(crate) fn snapshot(&self) -> UsageTotals {
+        *self.totals.lock().unwrap()
+    }
+
+    pub(crate) fn reset(&self) -> UsageTotals {
+        std::mem::take(&mut *self.totals.lock().unwrap())
+    }
+}
@@ -0,0 +1,59 @@
+use openai_rust_client::endpoints::{ChatMessage, CreateChatCompletionBuilder, FilePurpose, ListFiles, ListModels};
+use openai_rust_client::testing::{MockResponse, MockServer, MOCK_API_KEY};
+use openai_rust_client::{AzureDeployment, OpenAIClient, DEFAULT_AZURE_API_VERSION};
+use serde_json::json;
+
+fn azure_client(server: &MockServer) -> OpenAIClient {
+    let endpoint = server.base_url().trim_end_matches("/v1").to_string();
+    server
+        .client_builder()
+        .azure(AzureDeployment::new("resource", "my-deployment").endpoint(endpoint))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn sends_model_requests_to_the_deployment() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        "openai/deployments/my-deployment/chat/completions",
+        MockResponse::ok(&json!({
+            "id": "chatcmpl-1", "object": "chat.completion", "created": 0, "model": "gpt-4o-mini",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "hi"}, "finish_reason": "stop"}]
+        })),
+    );
+    let req = CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::user("hello"))
+        .build()
+        .unwrap();
+    azure_client(&server).send(&req).await.unwrap();
+
+    let received = &server.received()[0];
+    assert_eq!(received.endpoint, "openai/deployments/my-deployment/chat/completions");
+    assert_eq!(received.query, Some(format!("api-version={}", DEFAULT_AZURE_API_VERSION)));
+    assert_eq!(received.header("api-key"), Some(MOCK_API_KEY));
+    assert_eq!(received.header("authorization"), None);
+}
+
+#[tokio::test]
+async fn sends_other_requests_to_the_resource() {
+    let server = MockServer::start().await;
+    server.enqueue("openai/models", MockResponse::ok(&json!({"object": "list", "data": []})));
+    server.enqueue("openai/files", MockResponse::ok(&json!({"object": "list", "data": []})));
+    let client = azure_client(&server);
+    client.send(&ListModels {}).await.unwrap();
+    let list = ListFiles {
+        purpose: Some(FilePurpose::FineTune),
+        ..ListFiles::default()
+    };
+    client.send(&list).await.unwrap();
+
+    let received = server.received();
+    assert_eq!(received[0].endpoint, "openai/models");
+    assert_eq!(received[0].query, Some(format!("api-version={}", DEFAULT_AZURE_API_VERSION)));
+    assert_eq!(received[1].endpoint, "openai/files");
+    assert_eq!(
+        received[1].query,
+        Some(format!("purpose=fine-tune&api-version={}", DEFAULT_AZURE_API_VERSION))
+    );
+}
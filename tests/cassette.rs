@@ -0,0 +1,49 @@
+use openai_rust_client::endpoints::{ChatMessage, CreateChatCompletionBuilder, ListModels};
+use openai_rust_client::testing::{MockResponse, MockServer, MOCK_API_KEY};
+use openai_rust_client::{ApiKey, Cassette, CassetteMode, Error, OpenAIClient};
+use std::path::PathBuf;
+
+fn cassette_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("openai-rust-client-{}-{}.jsonl", name, std::process::id()))
+}
+
+#[tokio::test]
+async fn replays_recorded_interactions_without_the_server() {
+    let path = cassette_path("replay");
+    let server = MockServer::start().await;
+    server.enqueue(
+        "models",
+        MockResponse::error(401, "invalid_request_error", &format!("Incorrect API key provided: {}", MOCK_API_KEY)),
+    );
+    let recording = server
+        .client_builder()
+        .cassette(Cassette::open(&path, CassetteMode::Record).unwrap())
+        .build()
+        .unwrap();
+    let chat = CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::user("Say this is a test."))
+        .build()
+        .unwrap();
+    assert!(recording.send(&ListModels {}).await.is_err());
+    let recorded = recording.send(&chat).await.unwrap();
+    let base_url = server.base_url();
+    drop(server);
+
+    let cassette = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(cassette.lines().count(), 2);
+    assert!(!cassette.contains(MOCK_API_KEY));
+
+    let replaying = OpenAIClient::builder(ApiKey::new("sk-other".to_string()))
+        .base_url(base_url)
+        .cassette(Cassette::open(&path, CassetteMode::Replay).unwrap())
+        .build()
+        .unwrap();
+    let replayed = replaying.send(&chat).await.unwrap();
+    assert_eq!(replayed.id, recorded.id);
+    assert_eq!(replayed.choices[0].message.text(), recorded.choices[0].message.text());
+    assert!(matches!(replaying.send(&ListModels {}).await, Err(Error::ClientError { status: 401, .. })));
+    // each interaction is only replayed once
+    assert!(matches!(replaying.send(&chat).await, Err(Error::CassetteError { .. })));
+
+    std::fs::remove_file(&path).unwrap();
+}
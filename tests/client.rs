@@ -0,0 +1,319 @@
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use openai_rust_client::endpoints::{
+    ChatMessage, CreateChatCompletionBuilder, CreateCompletionBuilder, ListModels, Moderations, Prompt, StreamOptions,
+};
+use openai_rust_client::testing::ReceivedRequest;
+use openai_rust_client::testing::{MockResponse, MockServer, MOCK_API_KEY};
+use openai_rust_client::{Clock, Error, RetryPolicy};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A clock that records how long it was asked to sleep, without sleeping
+#[derive(Default)]
+struct RecordingClock {
+    sleeps: Mutex<Vec<Duration>>,
+}
+
+impl Clock for RecordingClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        self.sleeps.lock().unwrap().push(duration);
+        Box::pin(async {})
+    }
+}
+
+fn chat_request() -> openai_rust_client::endpoints::CreateChatCompletion {
+    CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::user("Say this is a test."))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn sends_authenticated_request_and_tracks_usage() {
+    let server = MockServer::start().await;
+    let client = server.client();
+
+    let resp = client.send(&chat_request()).await.unwrap();
+    assert_eq!(resp.choices[0].message.text(), Some("This is a test."));
+    assert_eq!(client.usage().total_tokens, 9);
+
+    let received = server.received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].method, "POST");
+    assert_eq!(received[0].endpoint, "chat/completions");
+    assert_eq!(received[0].header("authorization"), Some(format!("Bearer {}", MOCK_API_KEY).as_str()));
+    assert_eq!(received[0].json()["messages"][0]["role"], "user");
+}
+
+#[tokio::test]
+async fn sends_organization_project_and_custom_headers() {
+    let server = MockServer::start().await;
+    let client = server
+        .client_builder()
+        .organization("org-123")
+        .project("proj-456")
+        .header("X-Trace", "abc")
+        .build()
+        .unwrap();
+
+    client.send(&ListModels {}).await.unwrap();
+    let received = &server.received()[0];
+    assert_eq!(received.header("OpenAI-Organization"), Some("org-123"));
+    assert_eq!(received.header("OpenAI-Project"), Some("proj-456"));
+    assert_eq!(received.header("x-trace"), Some("abc"));
+}
+
+#[tokio::test]
+async fn retry_policy_and_clock_can_be_set_on_a_built_client() {
+    let server = MockServer::start().await;
+    server.enqueue("models", MockResponse::rate_limited(1));
+    server.enqueue("models", MockResponse::rate_limited(1));
+    let clock = Arc::new(RecordingClock::default());
+    let client = server
+        .client()
+        .with_retry_policy(RetryPolicy::default().max_attempts(2))
+        .with_clock(clock.clone());
+
+    assert!(client.send(&ListModels {}).await.is_err());
+    assert_eq!(server.received().len(), 2);
+    assert_eq!(*clock.sleeps.lock().unwrap(), vec![Duration::from_secs(1)]);
+}
+
+#[tokio::test]
+async fn retries_rate_limits_honouring_retry_after() {
+    let server = MockServer::start().await;
+    server.enqueue("models", MockResponse::rate_limited(2));
+    let clock = Arc::new(RecordingClock::default());
+    let client = server.client_builder().clock(clock.clone()).build().unwrap();
+
+    let models = client.send(&ListModels {}).await.unwrap();
+    assert_eq!(models.data[0].id, "mock-model");
+    assert_eq!(server.received().len(), 2);
+    assert_eq!(*clock.sleeps.lock().unwrap(), vec![Duration::from_secs(2)]);
+}
+
+#[tokio::test]
+async fn gives_up_when_retry_after_exceeds_the_cap() {
+    let server = MockServer::start().await;
+    server.enqueue("models", MockResponse::rate_limited(3600));
+    let clock = Arc::new(RecordingClock::default());
+    let client = server
+        .client_builder()
+        .clock(clock.clone())
+        .retry_policy(RetryPolicy::default().max_retry_after(Duration::from_secs(30)))
+        .build()
+        .unwrap();
+
+    match client.send(&ListModels {}).await {
+        Err(Error::ClientError { status, .. }) => assert_eq!(status, 429),
+        other => panic!("expected the rate limit error, got {:?}", other),
+    }
+    assert_eq!(server.received().len(), 1);
+    assert!(clock.sleeps.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn gives_up_on_server_errors_after_max_attempts() {
+    let server = MockServer::start().await;
+    server.handle("moderations", |_| MockResponse::server_error());
+    let client = server
+        .client_builder()
+        .retry_policy(RetryPolicy::default().max_attempts(3).base_delay(Duration::ZERO).jitter(0.0))
+        .build()
+        .unwrap();
+
+    let req = Moderations { input: vec!["hello".to_string()], model: None };
+    match client.send(&req).await {
+        Err(Error::ServerError { status, err, request_id }) => {
+            assert_eq!(status, 500);
+            assert_eq!(request_id.as_deref(), Some("req_mock"));
+            assert_eq!(err.kind.as_deref(), Some("server_error"));
+        }
+        other => panic!("expected a server error, got {:?}", other),
+    }
+    assert_eq!(server.received().len(), 3);
+}
+
+#[tokio::test]
+async fn does_not_retry_client_errors() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        "chat/completions",
+        MockResponse::error(400, "invalid_request_error", "messages is too long"),
+    );
+    let client = server.client();
+
+    match client.send(&chat_request()).await {
+        Err(Error::ClientError { status, err, .. }) => {
+            assert_eq!(status, 400);
+            assert_eq!(err.message, "messages is too long");
+        }
+        other => panic!("expected a client error, got {:?}", other),
+    }
+    assert_eq!(server.received().len(), 1);
+}
+
+#[tokio::test]
+async fn parses_errors_with_numeric_codes() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        "models",
+        MockResponse::json(429, &serde_json::json!({"error": {"message": "slow down", "type": null, "code": 429}})),
+    );
+    let client = server.client_builder().retry_policy(RetryPolicy::none()).build().unwrap();
+
+    match client.send(&ListModels {}).await {
+        Err(Error::ClientError { status, err, .. }) => {
+            assert_eq!(status, 429);
+            assert_eq!(err.message, "slow down");
+            assert_eq!(err.code.as_deref(), Some("429"));
+        }
+        other => panic!("expected a client error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn times_out_slow_responses() {
+    let server = MockServer::start().await;
+    server.enqueue("models", MockResponse::ok(&()).delay(Duration::from_secs(5)));
+    let client = server
+        .client_builder()
+        .timeout(Duration::from_millis(50))
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    assert!(matches!(client.send(&ListModels {}).await, Err(Error::HttpError { .. })));
+}
+
+#[tokio::test]
+async fn streams_completion_deltas() {
+    let server = MockServer::start().await;
+    let client = server.client();
+    let req = CreateCompletionBuilder::new("davinci-002")
+        .prompt(Prompt::One { one: "Say this is a test.".to_string() })
+        .stream(true)
+        .build()
+        .unwrap();
+
+    let deltas: Vec<_> = client.send_stream(&req).await.unwrap().collect().await;
+    let text: String = deltas.into_iter().map(|d| d.unwrap().text).collect();
+    assert_eq!(text, "This is a test.");
+    assert_eq!(server.received()[0].json()["stream"], true);
+}
+
+#[tokio::test]
+async fn records_usage_from_streamed_responses() {
+    let server = MockServer::start().await;
+    let client = server.client();
+    let req = CreateCompletionBuilder::new("davinci-002")
+        .prompt(Prompt::One { one: "Say this is a test.".to_string() })
+        .stream(true)
+        .stream_options(StreamOptions { include_usage: true })
+        .build()
+        .unwrap();
+
+    let deltas: Vec<_> = client.send_stream(&req).await.unwrap().collect().await;
+    assert_eq!(deltas.len(), 2);
+    let usage = client.usage();
+    assert_eq!((usage.requests, usage.total_tokens), (1, 9));
+}
+
+#[tokio::test]
+async fn records_usage_from_streamed_chat_responses() {
+    let server = MockServer::start().await;
+    let client = server.client();
+    let req = CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::user("Say this is a test."))
+        .stream(true)
+        .stream_options(StreamOptions { include_usage: true })
+        .build()
+        .unwrap();
+
+    let deltas: Vec<_> = client.send_stream(&req).await.unwrap().collect().await;
+    assert_eq!(deltas.len(), 2);
+    assert_eq!(server.received()[0].json()["stream_options"]["include_usage"], true);
+    let usage = client.usage();
+    assert_eq!((usage.requests, usage.total_tokens), (1, 9));
+}
+
+#[tokio::test]
+async fn returns_response_metadata() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        "chat/completions",
+        MockResponse::ok(&serde_json::json!({
+            "id": "chatcmpl-1", "object": "chat.completion", "created": 0, "model": "gpt-4o-mini",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "hi"}, "finish_reason": "stop"}]
+        }))
+        .header("openai-processing-ms", "250")
+        .header("openai-model", "gpt-4o-mini-2024-07-18")
+        .header("x-ratelimit-remaining-requests", "499")
+        .header("x-ratelimit-remaining-tokens", "199000"),
+    );
+    let client = server.client();
+
+    let resp = client.send_with_meta(&chat_request()).await.unwrap();
+    assert_eq!(resp.choices[0].message.text(), Some("hi"));
+    assert_eq!(resp.meta.status, 200);
+    assert_eq!(resp.meta.request_id.as_deref(), Some("req_mock"));
+    assert_eq!(resp.meta.processing_time, Some(Duration::from_millis(250)));
+    assert_eq!(resp.meta.model.as_deref(), Some("gpt-4o-mini-2024-07-18"));
+    assert_eq!(resp.meta.remaining_requests, Some(499));
+    assert_eq!(resp.meta.remaining_tokens, Some(199000));
+}
+
+#[tokio::test]
+async fn failed_requests_carry_the_request_id() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        "chat/completions",
+        MockResponse::error(400, "invalid_request_error", "messages is too long").header("x-request-id", "req_123"),
+    );
+    let client = server.client();
+
+    let err = client.send(&chat_request()).await.unwrap_err();
+    assert_eq!(err.request_id(), Some("req_123"));
+    assert!(err.to_string().ends_with("request id req_123"), "{}", err);
+}
+
+#[tokio::test]
+async fn sends_batches_in_order_with_per_item_results() {
+    let server = MockServer::start().await;
+    // later inputs answer sooner, and "2" fails
+    server.handle("moderations", |req: &ReceivedRequest| {
+        let input = req.json()["input"][0].as_str().unwrap().parse::<u64>().unwrap();
+        if input == 2 {
+            return MockResponse::error(400, "invalid_request_error", "bad input");
+        }
+        MockResponse::ok(&serde_json::json!({"id": format!("modr-{}", input), "model": "m", "results": []}))
+            .delay(Duration::from_millis(50 * (5 - input)))
+    });
+    let client = server.client();
+    let requests = (0..5).map(|i| Moderations {
+        input: vec![i.to_string()],
+        model: None,
+    });
+
+    let results: Vec<_> = client.send_all(requests, 3).collect().await;
+    let ids: Vec<_> = results.iter().map(|r| r.as_ref().map(|m| m.id.as_str()).ok()).collect();
+    assert_eq!(ids, vec![Some("modr-0"), Some("modr-1"), None, Some("modr-3"), Some("modr-4")]);
+    assert!(matches!(results[2], Err(Error::ClientError { status: 400, .. })));
+}
+
+#[tokio::test]
+async fn dropping_a_batch_stops_sending() {
+    let server = MockServer::start().await;
+    let client = server.client();
+    let requests = (0..10).map(|_| ListModels {});
+
+    let first: Vec<_> = client.send_all(requests, 2).take(1).collect().await;
+    assert!(first[0].is_ok());
+    // only the first window of requests was ever started
+    assert!(server.received().len() <= 2);
+}
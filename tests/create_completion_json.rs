@@ -0,0 +1,151 @@
+//! Golden JSON for every field of CreateCompletion, checked in both directions
+
+use openai_rust_client::endpoints::{CreateCompletion, CreateCompletionBuilder, Prompt, Stop, StreamOptions};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+fn builder() -> CreateCompletionBuilder {
+    CreateCompletionBuilder::new("gpt-3.5-turbo-instruct")
+}
+
+/// Checks the request serializes to `expected`, and that deserializing `expected` gives the same request back
+fn assert_golden(cc: CreateCompletion, expected: Value) {
+    assert_eq!(serde_json::to_value(&cc).unwrap(), expected);
+    let round_tripped: CreateCompletion = serde_json::from_value(expected.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&round_tripped).unwrap(), expected);
+}
+
+#[test]
+fn model_only() {
+    assert_golden(builder().build().unwrap(), json!({"model": "gpt-3.5-turbo-instruct"}));
+}
+
+#[test]
+fn prompt_one() {
+    let cc = builder().prompt(Prompt::One { one: "hello".to_string() }).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "prompt": "hello"}));
+}
+
+#[test]
+fn prompt_many() {
+    let cc = builder()
+        .prompt(Prompt::Many { many: vec!["a".to_string(), "b".to_string()] })
+        .build()
+        .unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "prompt": ["a", "b"]}));
+}
+
+#[test]
+fn suffix() {
+    let cc = builder().suffix("end".to_string()).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "suffix": "end"}));
+}
+
+#[test]
+fn max_tokens() {
+    let cc = builder().max_tokens(12).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "max_tokens": 12}));
+}
+
+#[test]
+fn temperature() {
+    let cc = builder().temperature(0.5).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "temperature": 0.5}));
+}
+
+#[test]
+fn top_p() {
+    let cc = builder().top_p(0.25).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "top_p": 0.25}));
+}
+
+#[test]
+fn n() {
+    let cc = builder().n(3).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "n": 3}));
+}
+
+#[test]
+fn stream_and_stream_options() {
+    let cc = builder()
+        .stream(true)
+        .stream_options(StreamOptions { include_usage: true })
+        .build()
+        .unwrap();
+    assert_golden(
+        cc,
+        json!({"model": "gpt-3.5-turbo-instruct", "stream": true, "stream_options": {"include_usage": true}}),
+    );
+}
+
+#[test]
+fn log_probs() {
+    let cc = builder().log_probs(5).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "logprobs": 5}));
+}
+
+#[test]
+fn echo() {
+    let cc = builder().echo(true).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "echo": true}));
+}
+
+#[test]
+fn stop_one() {
+    let cc = builder().stop(Stop::One { one: "\n".to_string() }).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "stop": "\n"}));
+}
+
+#[test]
+fn stop_many() {
+    let cc = builder()
+        .stop(Stop::Many { many: vec!["\n".to_string(), "END".to_string()] })
+        .build()
+        .unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "stop": ["\n", "END"]}));
+}
+
+#[test]
+fn presence_penalty() {
+    let cc = builder().presence_penalty(-1.5).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "presence_penalty": -1.5}));
+}
+
+#[test]
+fn frequency_penalty() {
+    let cc = builder().frequency_penalty(1.5).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "frequency_penalty": 1.5}));
+}
+
+#[test]
+fn best_of() {
+    let cc = builder().best_of(4).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "best_of": 4}));
+}
+
+#[test]
+fn logit_bias() {
+    let mut bias = HashMap::new();
+    bias.insert("50256".to_string(), -100.0);
+    let cc = builder().logit_bias(bias).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "logit_bias": {"50256": -100.0}}));
+}
+
+#[test]
+fn seed() {
+    let cc = builder().seed(42).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "seed": 42}));
+}
+
+#[test]
+fn user() {
+    let cc = builder().user("user-1234".to_string()).build().unwrap();
+    assert_golden(cc, json!({"model": "gpt-3.5-turbo-instruct", "user": "user-1234"}));
+}
+
+#[test]
+fn null_prompt_and_stop_deserialize_as_absent() {
+    let cc: CreateCompletion =
+        serde_json::from_value(json!({"model": "gpt-3.5-turbo-instruct", "prompt": null, "stop": null})).unwrap();
+    assert_eq!(serde_json::to_value(&cc).unwrap(), json!({"model": "gpt-3.5-turbo-instruct"}));
+}
@@ -0,0 +1,93 @@
+use base64::Engine as _;
+use openai_rust_client::endpoints::{
+    cosine_similarity, decode_base64_vector, dot_product, CreateEmbedding, EmbeddingInput, EncodingFormat,
+};
+use openai_rust_client::testing::{MockResponse, MockServer};
+use openai_rust_client::Error;
+use serde_json::json;
+
+fn embedding_response(data: serde_json::Value) -> MockResponse {
+    MockResponse::ok(&json!({
+        "object": "list", "model": "text-embedding-3-small", "data": data,
+        "usage": {"prompt_tokens": 4, "total_tokens": 4}
+    }))
+}
+
+fn base64_vector(vector: &[f32]) -> String {
+    let bytes: Vec<u8> = vector.iter().flat_map(|f| f.to_le_bytes()).collect();
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+#[tokio::test]
+async fn returns_float_vectors_in_input_order() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        "embeddings",
+        embedding_response(json!([
+            {"object": "embedding", "index": 1, "embedding": [0.0, 1.0]},
+            {"object": "embedding", "index": 0, "embedding": [1.0, 0.0]},
+        ])),
+    );
+    let client = server.client();
+    let req = CreateEmbedding::new(
+        "text-embedding-3-small",
+        EmbeddingInput::Many { many: vec!["a".to_string(), "b".to_string()] },
+    );
+    let resp = client.send(&req).await.unwrap();
+    assert_eq!(client.usage().total_tokens, 4);
+    assert_eq!(resp.into_vectors(), vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+
+    let body = server.received()[0].json();
+    assert_eq!(body, json!({"model": "text-embedding-3-small", "input": ["a", "b"]}));
+}
+
+#[tokio::test]
+async fn decodes_base64_vectors() {
+    let server = MockServer::start().await;
+    let vector = [0.25, -1.5, 3.0];
+    server.enqueue(
+        "embeddings",
+        embedding_response(json!([{"object": "embedding", "index": 0, "embedding": base64_vector(&vector)}])),
+    );
+    let client = server.client();
+    let mut req = CreateEmbedding::new("text-embedding-3-small", EmbeddingInput::One { one: "a".to_string() });
+    req.encoding_format = Some(EncodingFormat::Base64);
+    let resp = client.send(&req).await.unwrap();
+    assert_eq!(resp.data[0].embedding, vector);
+    assert_eq!(server.received()[0].json()["encoding_format"], "base64");
+}
+
+#[tokio::test]
+async fn rejects_malformed_base64_vectors() {
+    assert!(decode_base64_vector("not base64!").unwrap_err().contains("invalid base64"));
+    let five_bytes = base64::engine::general_purpose::STANDARD.encode([0u8; 5]);
+    assert!(decode_base64_vector(&five_bytes).unwrap_err().contains("5 bytes"));
+
+    let server = MockServer::start().await;
+    server.enqueue(
+        "embeddings",
+        embedding_response(json!([{"object": "embedding", "index": 0, "embedding": five_bytes}])),
+    );
+    let req = CreateEmbedding::new("text-embedding-3-small", EmbeddingInput::One { one: "a".to_string() });
+    match server.client().send(&req).await {
+        Err(Error::DeserializeError { err }) => assert!(err.contains("whole number of f32s"), "{}", err),
+        other => panic!("expected a deserialize error, got {:?}", other),
+    }
+}
+
+#[test]
+fn computes_similarity_of_known_vectors() {
+    assert_eq!(dot_product(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]), 32.0);
+    assert!((cosine_similarity(&[1.0, 0.0], &[1.0, 0.0]) - 1.0).abs() < 1e-6);
+    assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 2.0]).abs() < 1e-6);
+    assert!((cosine_similarity(&[1.0, 1.0], &[-2.0, -2.0]) + 1.0).abs() < 1e-6);
+    assert!((cosine_similarity(&[3.0, 4.0], &[4.0, 3.0]) - 0.96).abs() < 1e-6);
+    assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+}
+
+#[test]
+fn omits_missing_input() {
+    let req = CreateEmbedding::new("text-embedding-3-small", EmbeddingInput::None);
+    let body = serde_json::to_value(&req).unwrap();
+    assert_eq!(body, json!({"model": "text-embedding-3-small"}));
+}
@@ -0,0 +1,153 @@
+use openai_rust_client::endpoints::{
+    DeleteFile, FilePurpose, ListFiles, ObjectKind, RetrieveFile, RetrieveFileContent, UploadFile,
+};
+use openai_rust_client::testing::{MockResponse, MockServer};
+use openai_rust_client::{Error, RetryPolicy};
+use std::cell::Cell;
+use std::io::Cursor;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// A reader that can be sent to another thread but not shared between them
+struct NotSync {
+    inner: Cursor<Vec<u8>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl AsyncRead for NotSync {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+#[tokio::test]
+async fn uploads_bytes_as_multipart_form() {
+    let server = MockServer::start().await;
+    let client = server.client();
+    let upload = UploadFile::from_bytes(FilePurpose::FineTune, "train.jsonl", "{\"prompt\": \"hi\"}\n");
+    let file = client.send(&upload).await.unwrap();
+    assert_eq!(file.filename, "train.jsonl");
+    assert_eq!(file.purpose, FilePurpose::FineTune);
+    assert_eq!(file.object, ObjectKind::File);
+
+    let req = &server.received()[0];
+    assert_eq!(req.method, "POST");
+    assert_eq!(req.endpoint, "files");
+    assert!(req.header("content-type").unwrap().starts_with("multipart/form-data; boundary="));
+    let body = String::from_utf8(req.body.clone()).unwrap();
+    assert!(body.contains("name=\"purpose\"\r\n\r\nfine-tune\r\n"));
+    assert!(body.contains("name=\"file\"; filename=\"train.jsonl\""));
+    assert!(body.contains("{\"prompt\": \"hi\"}\n"));
+}
+
+#[tokio::test]
+async fn streams_uploads_from_a_reader_without_retrying() {
+    let server = MockServer::start().await;
+    let client = server
+        .client_builder()
+        .retry_policy(RetryPolicy::default().max_attempts(2).base_delay(Duration::ZERO).jitter(0.0))
+        .build()
+        .unwrap();
+    server.enqueue("files", MockResponse::server_error());
+    let contents = vec![b'x'; 200 * 1024];
+    let upload = UploadFile::from_reader(FilePurpose::Batch, "batch.jsonl", Cursor::new(contents.clone()), None);
+    match client.send(&upload).await {
+        Err(Error::ServerError { status, .. }) => assert_eq!(status, 500),
+        other => panic!("expected the server error, got {:?}", other),
+    }
+
+    let received = server.received();
+    assert_eq!(received.len(), 1);
+    let body = &received[0].body;
+    assert!(body.windows(contents.len()).any(|w| w == &contents[..]));
+
+    let reader = NotSync { inner: Cursor::new(b"abc".to_vec()), _not_sync: PhantomData };
+    let upload = UploadFile::from_reader(FilePurpose::Batch, "batch.jsonl", reader, Some(3));
+    assert_eq!(client.send(&upload).await.unwrap().filename, "batch.jsonl");
+    assert!(server.received()[1].body.windows(3).any(|w| w == b"abc"));
+
+    // uploads from bytes can be sent again
+    server.enqueue("files", MockResponse::server_error());
+    let upload = UploadFile::from_bytes(FilePurpose::Batch, "batch.jsonl", "abc");
+    assert_eq!(client.send(&upload).await.unwrap().filename, "batch.jsonl");
+    assert_eq!(server.received().len(), 4);
+}
+
+#[tokio::test]
+async fn lists_retrieves_and_deletes_files() {
+    let server = MockServer::start().await;
+    let client = server.client();
+    let list = ListFiles {
+        purpose: Some(FilePurpose::FineTune),
+        limit: Some(20),
+        after: Some("file-abc".to_string()),
+    };
+    let files = client.send(&list).await.unwrap();
+    assert_eq!(files.data[0].id, "file-mock");
+    assert!(!files.has_more);
+    client.send(&ListFiles::default()).await.unwrap();
+
+    let file = client.send(&RetrieveFile { file_id: "file-abc".to_string() }).await.unwrap();
+    assert_eq!(file.id, "file-abc");
+    let deleted = client.send(&DeleteFile { file_id: "file-abc".to_string() }).await.unwrap();
+    assert!(deleted.deleted);
+    let awkward = ListFiles {
+        purpose: Some(FilePurpose::from("my purpose")),
+        limit: None,
+        after: Some("file&a=b".to_string()),
+    };
+    client.send(&awkward).await.unwrap();
+
+    let received = server.received();
+    assert_eq!(received[0].endpoint, "files");
+    assert_eq!(received[0].query.as_deref(), Some("purpose=fine-tune&limit=20&after=file-abc"));
+    assert_eq!(received[1].query, None);
+    assert_eq!(received[4].query.as_deref(), Some("purpose=my+purpose&after=file%26a%3Db"));
+    assert_eq!((received[2].method.as_str(), received[2].endpoint.as_str()), ("GET", "files/file-abc"));
+    assert_eq!((received[3].method.as_str(), received[3].endpoint.as_str()), ("DELETE", "files/file-abc"));
+}
+
+#[tokio::test]
+async fn escapes_file_ids_in_the_path() {
+    let server = MockServer::start().await;
+    let client = server.client();
+    let file = client.send(&RetrieveFile { file_id: "a/b?c#d".to_string() }).await.unwrap();
+    assert_eq!(file.id, "a%2Fb%3Fc%23d");
+    client.send(&DeleteFile { file_id: "file-abc/../file-xyz".to_string() }).await.unwrap();
+    client.send(&RetrieveFileContent { file_id: "../models?x=1".to_string() }).await.unwrap();
+
+    let received = server.received();
+    assert_eq!((received[0].endpoint.as_str(), received[0].query.as_deref()), ("files/a%2Fb%3Fc%23d", None));
+    assert_eq!(received[1].endpoint, "files/file-abc%2F..%2Ffile-xyz");
+    assert_eq!(received[2].endpoint, "files/..%2Fmodels%3Fx=1/content");
+    assert_eq!(received[2].query, None);
+
+    // `..` can't be escaped, so it isn't sent at all
+    match client.send(&DeleteFile { file_id: "..".to_string() }).await {
+        Err(Error::ConfigError { .. }) => {}
+        other => panic!("expected a config error, got {:?}", other),
+    }
+    assert_eq!(server.received().len(), 3);
+}
+
+#[tokio::test]
+async fn downloads_content_as_raw_bytes() {
+    let server = MockServer::start().await;
+    let client = server.client();
+    let content = client
+        .send(&RetrieveFileContent { file_id: "file-abc".to_string() })
+        .await
+        .unwrap();
+    assert_eq!(content, b"contents of file-abc");
+
+    let binary = vec![0, 159, 146, 150, 255];
+    server.enqueue("files/file-bin/content", MockResponse::raw(200, "application/octet-stream", binary.clone()));
+    let content = client
+        .send(&RetrieveFileContent { file_id: "file-bin".to_string() })
+        .await
+        .unwrap();
+    assert_eq!(content, binary);
+}
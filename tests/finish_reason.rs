@@ -0,0 +1,40 @@
+use openai_rust_client::endpoints::{CreateChatCompletionResponse, CreateCompletionResponse, FinishReason, ObjectKind};
+use serde_json::json;
+
+#[test]
+fn known_and_unknown_values() {
+    let reasons: Vec<FinishReason> =
+        serde_json::from_value(json!(["stop", "length", "content_filter", "tool_calls", "function_call"])).unwrap();
+    assert_eq!(
+        reasons,
+        vec![
+            FinishReason::Stop,
+            FinishReason::Length,
+            FinishReason::ContentFilter,
+            FinishReason::ToolCalls,
+            FinishReason::Unknown("function_call".to_string()),
+        ]
+    );
+    // unknown values round-trip unchanged
+    assert_eq!(serde_json::to_value(&reasons).unwrap()[4], json!("function_call"));
+    assert_eq!(FinishReason::ContentFilter.to_string(), "content_filter");
+}
+
+#[test]
+fn typed_in_responses() {
+    let resp: CreateCompletionResponse = serde_json::from_value(json!({
+        "id": "cmpl-1", "object": "text_completion", "created": 0, "model": "gpt-3.5-turbo-instruct",
+        "choices": [{"text": "hi", "index": 0, "logprobs": null, "finish_reason": "length"}]
+    }))
+    .unwrap();
+    assert_eq!(resp.object, ObjectKind::TextCompletion);
+    assert_eq!(resp.choices[0].finish_reason, Some(FinishReason::Length));
+
+    let resp: CreateChatCompletionResponse = serde_json::from_value(json!({
+        "id": "chatcmpl-1", "object": "chat.completion", "created": 0, "model": "gpt-4o-mini",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "hi"}, "finish_reason": "stop"}]
+    }))
+    .unwrap();
+    assert_eq!(resp.object, ObjectKind::ChatCompletion);
+    assert_eq!(resp.choices[0].finish_reason, Some(FinishReason::Stop));
+}
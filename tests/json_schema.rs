@@ -0,0 +1,141 @@
+use openai_rust_client::endpoints::{
+    json_schema_for, ChatMessage, CreateChatCompletionBuilder, FunctionDefinition, JsonSchemaFormat, ToolCall,
+    TypedTool,
+};
+use openai_rust_client::Error;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::json;
+
+/// Look up the current weather
+#[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+struct GetWeather {
+    city: String,
+    unit: Option<Unit>,
+    days: u32,
+}
+
+#[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Unit {
+    Celsius,
+    Fahrenheit,
+}
+
+fn call(name: &str, arguments: &str) -> ToolCall {
+    serde_json::from_value(json!({
+        "id": "call_1", "type": "function", "function": {"name": name, "arguments": arguments}
+    }))
+    .unwrap()
+}
+
+#[test]
+fn derives_strict_schemas() {
+    let schema = json_schema_for::<GetWeather>();
+    assert_eq!(schema["type"], "object");
+    assert_eq!(schema["additionalProperties"], false);
+    // optional fields are still required, but nullable
+    assert_eq!(schema["required"], json!(["city", "days", "unit"]));
+    assert!(schema.get("$schema").is_none());
+    // strict mode rejects formats like uint32
+    assert!(schema["properties"]["days"].get("format").is_none());
+    assert_eq!(schema["properties"]["days"]["type"], "integer");
+
+    let function = FunctionDefinition::from_type::<GetWeather, _>("get_weather");
+    assert_eq!(function.description.as_deref(), Some("Look up the current weather"));
+    assert_eq!(function.strict, Some(true));
+    assert!(function.parameters.unwrap().get("title").is_none());
+
+    let format = JsonSchemaFormat::from_type::<GetWeather, _>("weather");
+    assert_eq!(format.schema["required"], json!(["city", "days", "unit"]));
+}
+
+#[test]
+fn dispatches_typed_calls() {
+    let weather = TypedTool::<GetWeather>::new("get_weather");
+    let req = CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::user("Weather in Paris?"))
+        .tool(weather.tool())
+        .build()
+        .unwrap();
+    assert_eq!(serde_json::to_value(&req).unwrap()["tools"][0]["function"]["strict"], true);
+
+    let args = weather
+        .parse(&call("get_weather", r#"{"city": "Paris", "unit": "celsius", "days": 2}"#))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        args,
+        GetWeather {
+            city: "Paris".to_string(),
+            unit: Some(Unit::Celsius),
+            days: 2
+        }
+    );
+    assert!(weather.parse(&call("get_time", "{}")).is_none());
+    match weather.parse(&call("get_weather", r#"{"city": "Paris", "unit": "kelvin", "days": 2}"#)) {
+        Some(Err(Error::SchemaMismatch { name, .. })) => assert_eq!(name, "get_weather"),
+        other => panic!("expected a schema mismatch, got {:?}", other),
+    }
+}
+
+/// Draw a shape
+#[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+struct Draw {
+    shape: Shape,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+#[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum Shape {
+    Circle { radius: f64 },
+    Square { side: f64 },
+}
+
+#[derive(JsonSchema, Deserialize)]
+struct Tagged {
+    tags: std::collections::HashMap<String, String>,
+}
+
+#[test]
+fn uses_any_of_for_data_carrying_enums() {
+    let schema = json_schema_for::<Draw>();
+    let shape = &schema["properties"]["shape"];
+    assert!(shape.get("oneOf").is_none());
+    let variants = shape["anyOf"].as_array().unwrap();
+    assert_eq!(variants.len(), 2);
+    for variant in variants {
+        assert_eq!(variant["additionalProperties"], false);
+    }
+    assert_eq!(variants[0]["properties"]["circle"]["required"], json!(["radius"]));
+    assert_eq!(variants[0]["properties"]["circle"]["additionalProperties"], false);
+    assert!(!schema.to_string().contains("oneOf"));
+
+    let args: Draw = TypedTool::<Draw>::new("draw")
+        .parse(&call("draw", r#"{"shape": {"square": {"side": 2.0}}, "label": null}"#))
+        .unwrap()
+        .unwrap();
+    assert_eq!(args.shape, Shape::Square { side: 2.0 });
+}
+
+#[test]
+fn makes_defaulted_options_required_and_nullable() {
+    let schema = json_schema_for::<Draw>();
+    assert_eq!(schema["required"], json!(["label", "shape"]));
+    let label = &schema["properties"]["label"];
+    assert!(label.get("default").is_none());
+    assert_eq!(label["type"], json!(["string", "null"]));
+    assert!(!schema.to_string().contains("\"default\""));
+}
+
+#[test]
+fn turns_off_strict_for_maps() {
+    assert_eq!(FunctionDefinition::from_type::<Draw, _>("draw").strict, Some(true));
+    assert_eq!(FunctionDefinition::from_type::<Tagged, _>("tag").strict, Some(false));
+    assert_eq!(JsonSchemaFormat::from_type::<Tagged, _>("tags").strict, Some(false));
+
+    let args = TypedTool::<Tagged>::new("tag").parse(&call("tag", r#"{"tags": {"a": "b"}}"#)).unwrap().unwrap();
+    assert_eq!(args.tags["a"], "b");
+}
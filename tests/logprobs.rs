@@ -0,0 +1,59 @@
+use openai_rust_client::endpoints::Choice;
+use openai_rust_client::testing::{MockResponse, MockServer};
+use openai_rust_client::FilterLabel;
+use serde_json::json;
+
+fn echoed_choice() -> Choice {
+    serde_json::from_value(json!({
+        "text": "Hello world",
+        "index": 0,
+        "logprobs": {
+            "tokens": ["Hello", " world"],
+            "token_logprobs": [null, -0.5],
+            "top_logprobs": [null, {" world": -0.5, " there": -1.5, "!": -0.25}],
+            "text_offset": [0, 5]
+        },
+        "finish_reason": "length"
+    }))
+    .unwrap()
+}
+
+#[test]
+fn deserializes_logprobs_object() {
+    let lp = echoed_choice().log_probs.expect("logprobs should be present");
+    assert_eq!(lp.tokens, vec!["Hello", " world"]);
+    assert_eq!(lp.token_logprobs, vec![None, Some(-0.5)]);
+    assert_eq!(lp.text_offset, vec![0, 5]);
+}
+
+#[test]
+fn logprob_helpers() {
+    let lp = echoed_choice().log_probs.unwrap();
+    assert_eq!(lp.probability(0), None);
+    assert_eq!(lp.probability(1), Some((-0.5f32).exp()));
+    assert_eq!(lp.perplexity(), Some(0.5f32.exp()));
+    assert_eq!(lp.alternatives(1), vec![("!", -0.25), (" world", -0.5), (" there", -1.5)]);
+    assert_eq!(lp.rank(1), Some(1));
+    assert_eq!(lp.rank(0), None);
+}
+
+#[tokio::test]
+#[allow(deprecated)]
+async fn content_filter_reads_logprobs_for_unsafe_label() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        "completions",
+        MockResponse::ok(&json!({
+            "id": "cmpl-mock", "object": "text_completion", "created": 0, "model": "content-filter-alpha",
+            "choices": [{
+                "text": "2", "index": 0, "finish_reason": "length",
+                "logprobs": {
+                    "tokens": ["2"], "token_logprobs": [-0.1], "text_offset": [0],
+                    "top_logprobs": [{"2": -0.1, "0": -2.5, "1": -3.0}]
+                }
+            }]
+        })),
+    );
+    let label = openai_rust_client::filter_content("some text", &server.client()).await.unwrap();
+    assert_eq!(label, FilterLabel::Unsafe);
+}
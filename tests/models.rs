@@ -0,0 +1,27 @@
+use openai_rust_client::endpoints::{DeleteModel, RetrieveModel};
+use openai_rust_client::testing::MockServer;
+use openai_rust_client::Error;
+
+#[tokio::test]
+async fn retrieves_and_deletes_models_by_id() {
+    let server = MockServer::start().await;
+    let client = server.client();
+    let id = "ft:gpt-4o-mini:org:custom:abc123";
+    let model = client.send(&RetrieveModel { id: id.to_string() }).await.unwrap();
+    assert_eq!(model.id, id);
+    let deleted = client.send(&DeleteModel { id: "ft:a/../../files?x#y".to_string() }).await.unwrap();
+    assert!(deleted.deleted);
+
+    let received = server.received();
+    assert_eq!(received[0].method, "GET");
+    assert_eq!(received[0].endpoint, "models/ft:gpt-4o-mini:org:custom:abc123");
+    assert_eq!(received[1].method, "DELETE");
+    assert_eq!(received[1].endpoint, "models/ft:a%2F..%2F..%2Ffiles%3Fx%23y");
+    assert_eq!(received[1].query, None);
+
+    match client.send(&DeleteModel { id: ".".to_string() }).await {
+        Err(Error::ConfigError { .. }) => {}
+        other => panic!("expected a config error, got {:?}", other),
+    }
+    assert_eq!(server.received().len(), 2);
+}
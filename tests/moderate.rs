@@ -0,0 +1,78 @@
+use openai_rust_client::testing::{MockResponse, MockServer, ReceivedRequest};
+use openai_rust_client::{moderate_text_with, ModerationOptions};
+use serde_json::{json, Value};
+
+/// Flags any input containing "BAD" for violence
+fn flag_bad(req: &ReceivedRequest) -> MockResponse {
+    let categories = |v: Value, violence: Value| {
+        json!({
+            "hate": v, "hate/threatening": v, "self-harm": v, "sexual": v,
+            "sexual/minors": v, "violence": violence, "violence/graphic": v
+        })
+    };
+    let results: Vec<Value> = req.json()["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|input| {
+            let bad = input.as_str().unwrap().contains("BAD");
+            json!({
+                "categories": categories(json!(false), json!(bad)),
+                "category_scores": categories(json!(0.01), json!(if bad { 0.9 } else { 0.1 })),
+                "flagged": bad,
+            })
+        })
+        .collect();
+    MockResponse::ok(&json!({"id": "modr-mock", "model": "text-moderation-mock", "results": results}))
+}
+
+fn options() -> ModerationOptions {
+    ModerationOptions::default().chunk_chars(10).overlap_chars(3).batch_size(2)
+}
+
+#[tokio::test]
+async fn chunks_batches_and_aggregates() {
+    let server = MockServer::start().await;
+    server.handle("moderations", flag_bad);
+    let client = server.client();
+    let text = format!("{}BAD{}", "a".repeat(25), "b".repeat(30));
+
+    let verdict = moderate_text_with(&text, &options(), &client).await.unwrap();
+    // 58 characters in chunks starting every 7, two chunks per request
+    let received = server.received();
+    assert_eq!(received.len(), 4);
+    assert_eq!(received[0].json()["input"], json!(["aaaaaaaaaa", "aaaaaaaaaa"]));
+    assert!(verdict.flagged);
+    assert!(verdict.categories.violence);
+    assert!(!verdict.categories.hate);
+    assert_eq!(verdict.category_scores.violence, 0.9);
+    assert_eq!(verdict.category_scores.hate, 0.01);
+    assert_eq!(verdict.flagged_spans.len(), 1);
+    let span = &verdict.flagged_spans[0];
+    assert_eq!(&text[span.start..span.end], "aaaaBADbbb");
+}
+
+#[tokio::test]
+async fn merges_overlapping_flagged_chunks() {
+    let server = MockServer::start().await;
+    server.handle("moderations", flag_bad);
+    let client = server.client();
+    // "BAD" sits in the overlap of the first two chunks; multi-byte characters keep offsets honest
+    let text = format!("éééééééBAD{}", "é".repeat(20));
+
+    let verdict = moderate_text_with(&text, &options(), &client).await.unwrap();
+    assert_eq!(verdict.flagged_spans.len(), 1);
+    let span = &verdict.flagged_spans[0];
+    assert_eq!(span.start, 0);
+    assert_eq!(&text[span.start..span.end], "éééééééBADééééééé");
+}
+
+#[tokio::test]
+async fn empty_text_sends_nothing() {
+    let server = MockServer::start().await;
+    let client = server.client();
+
+    let verdict = moderate_text_with("", &options(), &client).await.unwrap();
+    assert!(!verdict.flagged);
+    assert!(server.received().is_empty());
+}
@@ -0,0 +1,138 @@
+use futures_util::future::BoxFuture;
+use openai_rust_client::endpoints::{
+    ChatMessage, CreateChatCompletionBuilder, CreateCompletionBuilder, ListModels, Prompt,
+};
+use openai_rust_client::testing::{MockResponse, MockServer};
+use openai_rust_client::{Clock, RateLimit};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A clock whose time only moves when something sleeps on it
+struct FakeClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+    sleeps: Mutex<Vec<Duration>>,
+}
+
+impl FakeClock {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+            sleeps: Mutex::new(vec![]),
+        })
+    }
+
+    fn slept(&self) -> Duration {
+        self.sleeps.lock().unwrap().iter().sum()
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        *self.elapsed.lock().unwrap() += duration;
+        self.sleeps.lock().unwrap().push(duration);
+        Box::pin(async {})
+    }
+}
+
+fn assert_about(actual: Duration, expected: Duration) {
+    let diff = actual.abs_diff(expected);
+    assert!(diff < Duration::from_millis(10), "expected about {:?}, got {:?}", expected, actual);
+}
+
+#[tokio::test]
+async fn waits_for_request_budget() {
+    let server = MockServer::start().await;
+    let clock = FakeClock::new();
+    let client = server
+        .client_builder()
+        .clock(clock.clone())
+        .rate_limit(RateLimit::new().requests_per_minute(2))
+        .build()
+        .unwrap();
+
+    client.send(&ListModels {}).await.unwrap();
+    client.send(&ListModels {}).await.unwrap();
+    assert_eq!(clock.slept(), Duration::ZERO);
+    // the bucket refills at one request every 30s
+    client.send(&ListModels {}).await.unwrap();
+    assert_about(clock.slept(), Duration::from_secs(30));
+}
+
+#[tokio::test]
+async fn waits_for_estimated_token_budget() {
+    let server = MockServer::start().await;
+    let clock = FakeClock::new();
+    let client = server
+        .client_builder()
+        .clock(clock.clone())
+        .rate_limit(RateLimit::new().tokens_per_minute(600))
+        .build()
+        .unwrap();
+    // 8 prompt characters is 2 tokens, plus 2 completions of up to 149 tokens each
+    let req = CreateCompletionBuilder::new("gpt-3.5-turbo-instruct")
+        .prompt(Prompt::One { one: "12345678".to_string() })
+        .max_tokens(149)
+        .n(2)
+        .build()
+        .unwrap();
+
+    client.send(&req).await.unwrap();
+    client.send(&req).await.unwrap();
+    assert_eq!(clock.slept(), Duration::ZERO);
+    client.send(&req).await.unwrap();
+    // 300 tokens at 10 per second
+    assert_about(clock.slept(), Duration::from_secs(30));
+}
+
+#[tokio::test]
+async fn assumes_long_replies_for_chat_without_max_tokens() {
+    let server = MockServer::start().await;
+    let clock = FakeClock::new();
+    let client = server
+        .client_builder()
+        .clock(clock.clone())
+        .rate_limit(RateLimit::new().tokens_per_minute(8204))
+        .build()
+        .unwrap();
+    // 2 tokens of text and 4 of framing, plus a reply of up to 4096 tokens
+    let req = CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::user("12345678"))
+        .build()
+        .unwrap();
+
+    client.send(&req).await.unwrap();
+    client.send(&req).await.unwrap();
+    assert_eq!(clock.slept(), Duration::ZERO);
+    client.send(&req).await.unwrap();
+    assert_about(clock.slept(), Duration::from_secs(30));
+}
+
+#[tokio::test]
+async fn calibrates_from_response_headers() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        "models",
+        MockResponse::ok(&json!({"object": "list", "data": []}))
+            .header("x-ratelimit-limit-requests", "60")
+            .header("x-ratelimit-remaining-requests", "0"),
+    );
+    let clock = FakeClock::new();
+    let client = server
+        .client_builder()
+        .clock(clock.clone())
+        .rate_limit(RateLimit::new().requests_per_minute(1000))
+        .build()
+        .unwrap();
+
+    client.send(&ListModels {}).await.unwrap();
+    // the server says the budget is spent and only refills at one request per second
+    client.send(&ListModels {}).await.unwrap();
+    assert_about(clock.slept(), Duration::from_secs(1));
+}
@@ -0,0 +1,105 @@
+use openai_rust_client::endpoints::{
+    ChatMessage, CreateChatCompletion, CreateChatCompletionBuilder, JsonSchemaFormat, ResponseFormat,
+};
+use openai_rust_client::testing::{MockResponse, MockServer};
+use openai_rust_client::Error;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// A city and its country
+#[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+struct City {
+    name: String,
+    country: String,
+}
+
+fn request() -> CreateChatCompletion {
+    CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::user("Name the capital of France"))
+        .response_format(JsonSchemaFormat::from_type::<City, _>("city"))
+        .build()
+        .unwrap()
+}
+
+fn reply(message: Value, finish_reason: &str) -> MockResponse {
+    MockResponse::ok(&json!({
+        "id": "chatcmpl-1", "object": "chat.completion", "created": 0, "model": "gpt-4o-mini",
+        "choices": [{"index": 0, "message": message, "finish_reason": finish_reason}]
+    }))
+}
+
+#[test]
+fn serializes_response_formats() {
+    let format = |f: ResponseFormat| {
+        let req = CreateChatCompletionBuilder::new("gpt-4o-mini")
+            .message(ChatMessage::user("Reply in JSON"))
+            .response_format(f)
+            .build()
+            .unwrap();
+        serde_json::to_value(&req).unwrap()["response_format"].clone()
+    };
+    assert_eq!(format(ResponseFormat::Text), json!({"type": "text"}));
+    assert_eq!(format(ResponseFormat::JsonObject), json!({"type": "json_object"}));
+    let schema = json!({"type": "object", "properties": {}, "additionalProperties": false, "required": []});
+    assert_eq!(
+        format(JsonSchemaFormat::new("empty", schema.clone()).strict(true).into()),
+        json!({"type": "json_schema", "json_schema": {"name": "empty", "schema": schema, "strict": true}})
+    );
+}
+
+#[tokio::test]
+async fn parses_structured_replies() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        "chat/completions",
+        reply(json!({"role": "assistant", "content": "{\"name\": \"Paris\", \"country\": \"France\"}"}), "stop"),
+    );
+    let client = server.client();
+
+    let city: City = client.send_structured(&request()).await.unwrap();
+    assert_eq!(
+        city,
+        City {
+            name: "Paris".to_string(),
+            country: "France".to_string()
+        }
+    );
+    let sent = server.received()[0].json();
+    assert_eq!(sent["response_format"]["json_schema"]["name"], "city");
+    assert_eq!(sent["response_format"]["json_schema"]["strict"], true);
+}
+
+#[tokio::test]
+async fn distinguishes_refusals_truncation_and_mismatches() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        "chat/completions",
+        reply(json!({"role": "assistant", "content": null, "refusal": "I can't help with that."}), "stop"),
+    );
+    server.enqueue(
+        "chat/completions",
+        reply(json!({"role": "assistant", "content": "{\"name\": \"Par"}), "length"),
+    );
+    server.enqueue(
+        "chat/completions",
+        reply(json!({"role": "assistant", "content": "{\"name\": \"Paris\"}"}), "stop"),
+    );
+    let client = server.client();
+
+    match client.send_structured::<City>(&request()).await {
+        Err(Error::Refusal { refusal }) => assert_eq!(refusal, "I can't help with that."),
+        other => panic!("expected a refusal, got {:?}", other),
+    }
+    match client.send_structured::<City>(&request()).await {
+        Err(Error::Truncated { content }) => assert_eq!(content, "{\"name\": \"Par"),
+        other => panic!("expected truncation, got {:?}", other),
+    }
+    match client.send_structured::<City>(&request()).await {
+        Err(Error::SchemaMismatch { name, err }) => {
+            assert_eq!(name, "city");
+            assert!(err.contains("country"), "{}", err);
+        }
+        other => panic!("expected a schema mismatch, got {:?}", other),
+    }
+}
@@ -0,0 +1,195 @@
+use openai_rust_client::endpoints::{
+    ChatMessage, CreateChatCompletion, CreateChatCompletionBuilder, FunctionDefinition, Tool, ToolChoice, TypedTool,
+};
+use openai_rust_client::testing::{MockResponse, MockServer, ReceivedRequest};
+use openai_rust_client::{Error, Step, ToolOutcome, ToolRunner};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Barrier;
+
+/// Look up the current weather
+#[derive(JsonSchema, Deserialize)]
+struct GetWeather {
+    city: String,
+}
+
+fn completion(message: Value) -> MockResponse {
+    MockResponse::ok(&json!({
+        "id": "chatcmpl-1", "object": "chat.completion", "created": 0, "model": "gpt-4o-mini",
+        "choices": [{"index": 0, "message": message, "finish_reason": "stop"}]
+    }))
+}
+
+fn tool_call(id: &str, name: &str, arguments: Value) -> Value {
+    json!({"id": id, "type": "function", "function": {"name": name, "arguments": arguments.to_string()}})
+}
+
+/// Asks for the given tool calls in reply to the user, then answers with the tool results joined together
+fn model(calls: Vec<Value>) -> impl Fn(&ReceivedRequest) -> MockResponse {
+    move |req| {
+        let messages = req.json()["messages"].as_array().unwrap().clone();
+        if messages.last().unwrap()["role"] == "user" {
+            return completion(json!({"role": "assistant", "content": null, "tool_calls": calls}));
+        }
+        let results: Vec<&str> = messages
+            .iter()
+            .filter(|m| m["role"] == "tool")
+            .map(|m| m["content"].as_str().unwrap())
+            .collect();
+        completion(json!({"role": "assistant", "content": results.join(" | ")}))
+    }
+}
+
+fn request() -> CreateChatCompletion {
+    CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::user("What's the weather and time in Paris?"))
+        .build()
+        .unwrap()
+}
+
+fn time_tool() -> Tool {
+    Tool::function(FunctionDefinition::new("get_time"))
+}
+
+#[tokio::test]
+async fn runs_tools_in_parallel_until_a_final_reply() {
+    let server = MockServer::start().await;
+    server.handle(
+        "chat/completions",
+        model(vec![
+            tool_call("call_1", "get_weather", json!({"city": "Paris"})),
+            tool_call("call_2", "get_time", json!({})),
+            tool_call("call_3", "get_stock_price", json!({})),
+        ]),
+    );
+    let client = server.client();
+    // each handler waits for the other, so this only finishes if they run at the same time
+    let barrier = Arc::new(Barrier::new(2));
+    let weather_barrier = barrier.clone();
+    let runner = ToolRunner::new(&client)
+        .timeout(Duration::from_secs(5))
+        .register_typed(TypedTool::<GetWeather>::new("get_weather"), move |args| {
+            let barrier = weather_barrier.clone();
+            async move {
+                barrier.wait().await;
+                Ok(format!("18C in {}", args.city))
+            }
+        })
+        .register(time_tool(), move |_| {
+            let barrier = barrier.clone();
+            async move {
+                barrier.wait().await;
+                Ok("noon".to_string())
+            }
+        });
+
+    let run = runner.run(request()).await;
+    let reply = run.reply.unwrap();
+    assert_eq!(reply.text(), Some("18C in Paris | noon | Error: there is no such tool"));
+    // user, assistant with tool calls, three tool results, final assistant
+    assert_eq!(run.messages.len(), 6);
+    let sent = server.received()[0].json();
+    let mut tools: Vec<&str> = sent["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["function"]["name"].as_str().unwrap())
+        .collect();
+    tools.sort_unstable();
+    assert_eq!(tools, vec!["get_time", "get_weather"]);
+
+    let outcomes: Vec<&ToolOutcome> = run
+        .transcript
+        .iter()
+        .filter_map(|step| match step {
+            Step::ToolCall { outcome, .. } => Some(outcome),
+            Step::Reply { .. } => None,
+        })
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            &ToolOutcome::Success("18C in Paris".to_string()),
+            &ToolOutcome::Success("noon".to_string()),
+            &ToolOutcome::UnknownTool,
+        ]
+    );
+    assert_eq!(run.transcript.len(), 5);
+}
+
+#[tokio::test]
+async fn times_out_slow_tools() {
+    let server = MockServer::start().await;
+    server.handle(
+        "chat/completions",
+        model(vec![tool_call("call_1", "get_time", json!({}))]),
+    );
+    let client = server.client();
+    let runner =
+        ToolRunner::new(&client).register_with_timeout(time_tool(), Some(Duration::from_millis(50)), |_| async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok("too late".to_string())
+        });
+
+    let run = runner.run(request()).await;
+    assert_eq!(run.reply.unwrap().text(), Some("Error: the tool timed out after 50ms"));
+    assert!(matches!(
+        run.transcript[1],
+        Step::ToolCall {
+            outcome: ToolOutcome::TimedOut(_),
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn forced_tool_choice_only_applies_to_the_first_request() {
+    let server = MockServer::start().await;
+    // like the API, always calls a tool while one is required
+    server.handle("chat/completions", |req: &ReceivedRequest| {
+        if req.json()["tool_choice"] == "required" {
+            completion(
+                json!({"role": "assistant", "content": null, "tool_calls": [tool_call("call_1", "get_time", json!({}))]}),
+            )
+        } else {
+            completion(json!({"role": "assistant", "content": "It's noon."}))
+        }
+    });
+    let client = server.client();
+    let runner = ToolRunner::new(&client).register(time_tool(), |_| async { Ok("noon".to_string()) });
+    let req = CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::user("What time is it?"))
+        .tool(time_tool())
+        .tool_choice(ToolChoice::Required)
+        .build()
+        .unwrap();
+
+    let run = runner.run(req).await;
+    assert_eq!(run.reply.unwrap().text(), Some("It's noon."));
+    let received = server.received();
+    assert_eq!(received.len(), 2);
+    assert_eq!(received[1].json()["tool_choice"], "auto");
+}
+
+#[tokio::test]
+async fn stops_after_max_iterations() {
+    let server = MockServer::start().await;
+    server.handle("chat/completions", |_: &ReceivedRequest| {
+        completion(
+            json!({"role": "assistant", "content": null, "tool_calls": [tool_call("call_1", "get_time", json!({}))]}),
+        )
+    });
+    let client = server.client();
+    let runner = ToolRunner::new(&client)
+        .max_iterations(3)
+        .register(time_tool(), |_| async { Ok("noon".to_string()) });
+
+    let run = runner.run(request()).await;
+    assert!(matches!(run.reply, Err(Error::MaxIterations { iterations: 3 })));
+    assert_eq!(server.received().len(), 3);
+    // each iteration has a reply and a tool call
+    assert_eq!(run.transcript.len(), 6);
+}
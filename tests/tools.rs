@@ -0,0 +1,138 @@
+use openai_rust_client::endpoints::{
+    BuildError, ChatMessage, CreateChatCompletionBuilder, FinishReason, FunctionDefinition, Tool, ToolChoice,
+};
+use openai_rust_client::testing::{MockResponse, MockServer};
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct WeatherArgs {
+    city: String,
+    unit: Option<String>,
+}
+
+fn weather_tool() -> Tool {
+    Tool::function(
+        FunctionDefinition::new("get_weather")
+            .description("Current weather for a city")
+            .parameters(json!({
+                "type": "object",
+                "properties": {"city": {"type": "string"}, "unit": {"type": "string"}},
+                "required": ["city"]
+            })),
+    )
+}
+
+#[test]
+fn serializes_tools_and_tool_choice() {
+    let choices = vec![
+        (ToolChoice::Auto, json!("auto")),
+        (ToolChoice::None, json!("none")),
+        (ToolChoice::Required, json!("required")),
+        (
+            ToolChoice::Function("get_weather".to_string()),
+            json!({"type": "function", "function": {"name": "get_weather"}}),
+        ),
+    ];
+    for (choice, expected) in choices {
+        let req = CreateChatCompletionBuilder::new("gpt-4o-mini")
+            .message(ChatMessage::user("Weather in Paris?"))
+            .tool(weather_tool())
+            .tool_choice(choice)
+            .build()
+            .unwrap();
+        let body = serde_json::to_value(&req).unwrap();
+        assert_eq!(body["tool_choice"], expected);
+        assert_eq!(body["tools"][0]["type"], "function");
+        assert_eq!(body["tools"][0]["function"]["name"], "get_weather");
+        assert_eq!(body["tools"][0]["function"]["parameters"]["required"], json!(["city"]));
+    }
+}
+
+#[test]
+fn tool_choice_must_name_a_tool() {
+    let errors = CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::user("hi"))
+        .tool(weather_tool())
+        .tool_choice(ToolChoice::Function("get_time".to_string()))
+        .build()
+        .unwrap_err();
+    assert_eq!(errors.0, vec![BuildError::UnknownTool { name: "get_time".to_string() }]);
+
+    let errors = CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::user("hi"))
+        .tool_choice(ToolChoice::Required)
+        .build()
+        .unwrap_err();
+    assert_eq!(errors.0, vec![BuildError::Missing { field: "tools" }]);
+}
+
+#[tokio::test]
+async fn parses_tool_calls_and_sends_results_back() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        "chat/completions",
+        MockResponse::ok(&json!({
+            "id": "chatcmpl-1", "object": "chat.completion", "created": 0, "model": "gpt-4o-mini",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1", "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\": \"Paris\"}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        })),
+    );
+    let client = server.client();
+    let question = ChatMessage::user("Weather in Paris?");
+    let req = CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(question.clone())
+        .tool(weather_tool())
+        .build()
+        .unwrap();
+
+    let resp = client.send(&req).await.unwrap();
+    let choice = &resp.choices[0];
+    assert_eq!(choice.finish_reason, Some(FinishReason::ToolCalls));
+    let calls = choice.message.tool_calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].function.name, "get_weather");
+    let args: WeatherArgs = calls[0].parse_arguments().unwrap();
+    assert_eq!(
+        args,
+        WeatherArgs {
+            city: "Paris".to_string(),
+            unit: None
+        }
+    );
+
+    let follow_up = CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .messages(vec![
+            question,
+            choice.message.clone(),
+            ChatMessage::tool(&calls[0].id, "18C and sunny"),
+        ])
+        .tool(weather_tool())
+        .build()
+        .unwrap();
+    client.send(&follow_up).await.unwrap();
+    let sent = server.received()[1].json();
+    assert_eq!(sent["messages"][1]["tool_calls"][0]["type"], "function");
+    assert_eq!(sent["messages"][1]["tool_calls"][0]["id"], "call_1");
+    assert_eq!(sent["messages"][2], json!({"role": "tool", "content": "18C and sunny", "tool_call_id": "call_1"}));
+}
+
+#[test]
+fn reports_malformed_arguments() {
+    let call: openai_rust_client::endpoints::ToolCall = serde_json::from_value(json!({
+        "id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": "{\"town\": 1}"}
+    }))
+    .unwrap();
+    let err = call.parse_arguments::<WeatherArgs>().unwrap_err();
+    assert!(err.to_string().contains("get_weather"));
+}
@@ -0,0 +1,99 @@
+use openai_rust_client::endpoints::{
+    BuildError, ChatMessage, CreateChatCompletionBuilder, CreateCompletion, CreateCompletionBuilder, Stop,
+    StreamOptions,
+};
+
+#[test]
+fn reports_every_violation() {
+    let errors = CreateCompletionBuilder::new("gpt-3.5-turbo-instruct")
+        .temperature(2.5)
+        .presence_penalty(3.0)
+        .max_tokens(5000)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        errors.0,
+        vec![
+            BuildError::MaxTokensExceeded {
+                max_tokens: 5000,
+                limit: 4096,
+                model: "gpt-3.5-turbo-instruct".to_string()
+            },
+            BuildError::OutOfRange {
+                field: "temperature",
+                value: 2.5,
+                min: 0.0,
+                max: 2.0
+            },
+            BuildError::OutOfRange {
+                field: "presence_penalty",
+                value: 3.0,
+                min: -2.0,
+                max: 2.0
+            },
+        ]
+    );
+    assert!(errors.to_string().contains("presence_penalty must be in range [-2, 2], got 3"));
+}
+
+#[test]
+fn accepts_the_documented_limits() {
+    CreateCompletionBuilder::new("gpt-3.5-turbo-instruct")
+        .temperature(2.0)
+        .max_tokens(4096)
+        .n(2)
+        .best_of(3)
+        .build()
+        .unwrap();
+    // no known limit for this model, so only the API can reject it
+    CreateCompletionBuilder::new("some-future-model").max_tokens(60000).build().unwrap();
+    CreateCompletionBuilder::new("davinci-002").max_tokens(16000).build().unwrap();
+}
+
+#[test]
+fn best_of_must_be_greater_than_n() {
+    let errors = CreateCompletionBuilder::new("gpt-3.5-turbo-instruct")
+        .n(3)
+        .best_of(3)
+        .build()
+        .unwrap_err();
+    assert_eq!(errors.0, vec![BuildError::BestOfNotGreaterThanN { best_of: 3, n: 3 }]);
+    assert!(errors.concerns("best_of"));
+}
+
+#[test]
+fn rejects_invalid_deserialized_requests() {
+    let err = serde_json::from_str::<CreateCompletion>(r#"{"model": "gpt-3.5-turbo-instruct", "top_p": 1.5}"#)
+        .unwrap_err();
+    assert!(err.to_string().contains("top_p"), "{}", err);
+    let req: CreateCompletion = serde_json::from_str(r#"{"model": "gpt-3.5-turbo-instruct", "top_p": 0.5}"#).unwrap();
+    assert!(req.validate().is_ok());
+}
+
+#[test]
+fn chat_requires_messages_and_checks_stop() {
+    let errors = CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .stop(Stop::Many {
+            many: vec!["a".into(), "b".into(), "c".into(), "d".into(), "e".into()],
+        })
+        .stream_options(StreamOptions { include_usage: true })
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        errors.0,
+        vec![
+            BuildError::Missing { field: "messages" },
+            BuildError::TooMany {
+                field: "stop",
+                count: 5,
+                max: 4
+            },
+            BuildError::RequiresStream { field: "stream_options" },
+        ]
+    );
+    CreateChatCompletionBuilder::new("gpt-4o-mini")
+        .message(ChatMessage::user("hi"))
+        .max_tokens(16384)
+        .build()
+        .unwrap();
+}